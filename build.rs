@@ -1,10 +1,11 @@
 use self::shared::ModifierSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write;
 use std::iter;
 use std::iter::Peekable;
 use std::path::Path;
 
-type StrResult<T> = Result<T, String>;
+type SourceResult<'a, T> = Result<T, Error<'a>>;
 
 #[path = "src/shared.rs"]
 mod shared;
@@ -22,7 +23,7 @@ impl<'a> Module<'a> {
 /// A definition bound in a module, with metadata.
 struct Binding<'a> {
     def: Def<'a>,
-    deprecation: Option<&'a str>,
+    meta: Meta<'a>,
 }
 
 /// A definition in a module.
@@ -31,10 +32,73 @@ enum Def<'a> {
     Module(Module<'a>),
 }
 
-/// A symbol, either a leaf or with modifiers with optional deprecation.
+/// A symbol, either a leaf or with modifiers with metadata.
 enum Symbol<'a> {
-    Single(String),
-    Multi(Vec<(ModifierSet<&'a str>, String, Option<&'a str>)>),
+    Single(Value<'a>),
+    Multi(Vec<(ModifierSet<&'a str>, Value<'a>, Meta<'a>)>),
+}
+
+/// Metadata for a definition or variant, given by the lines preceding it.
+#[derive(Debug, Default, Clone)]
+struct Meta<'a> {
+    /// The deprecation message, from `@deprecated:`.
+    deprecation: Option<&'a str>,
+    /// The lines of the description, from `///` comments.
+    doc: Vec<&'a str>,
+    /// The section of the file a top-level definition is in, from the last
+    /// comment that starts after a blank line at the top level, alongside the
+    /// index of the section in the file.
+    section: Option<(usize, &'a str)>,
+    /// The cargo feature that is required for a definition, from `@feature:`.
+    feature: Option<&'a str>,
+    /// The ASCII approximation of a value, from `@ascii:`.
+    ascii: Option<&'a str>,
+    /// The speech text, from `@speech:`.
+    speech: Option<&'a str>,
+    /// The comma-separated search tags, from `@tags:`.
+    tags: Option<&'a str>,
+}
+
+impl<'a> Meta<'a> {
+    /// The description, with the lines joined.
+    fn description(&self) -> Option<String> {
+        (!self.doc.is_empty()).then(|| self.doc.join("\n"))
+    }
+
+    /// The search tags.
+    fn tags(&self) -> impl Iterator<Item = String> + use<'a> {
+        let tags = self.tags.into_iter().flat_map(|tags| tags.split(','));
+        tags.map(|tag| tag.trim().to_lowercase())
+    }
+}
+
+/// The value of a symbol or variant.
+#[derive(Debug, Clone)]
+enum Value<'a> {
+    /// A string, with all escapes decoded.
+    Literal(String),
+    /// An alias for another variant, given by its path relative to the
+    /// top-level module of the file. Holds the target's value once the alias
+    /// is resolved.
+    Alias(&'a str, Option<String>),
+}
+
+impl<'a> Value<'a> {
+    /// The target of the value if it is an alias.
+    fn target(&self) -> Option<&'a str> {
+        match self {
+            Self::Literal(_) => None,
+            Self::Alias(target, _) => Some(target),
+        }
+    }
+
+    /// The string value, which must already be resolved for aliases.
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Literal(value) | Self::Alias(_, Some(value)) => value,
+            Self::Alias(target, None) => panic!("unresolved alias for `{target}`"),
+        }
+    }
 }
 
 /// A single line during parsing.
@@ -42,112 +106,760 @@ enum Symbol<'a> {
 enum Line<'a> {
     Blank,
     Deprecated(&'a str),
+    Feature(&'a str),
+    Ascii(&'a str),
+    Speech(&'a str),
+    Tags(&'a str),
     ModuleStart(&'a str),
-    ModuleEnd,
-    Symbol(&'a str, Option<String>),
-    Variant(ModifierSet<&'a str>, String),
-    Eof,
+    ModuleEnd(&'a str),
+    Symbol(&'a str, Option<Value<'a>>),
+    Variant(ModifierSet<&'a str>, Value<'a>),
 }
 
 #[derive(Debug, Clone)]
 enum Declaration<'a> {
-    ModuleStart(&'a str, Option<&'a str>),
-    ModuleEnd,
-    Symbol(&'a str, Option<String>, Option<&'a str>),
-    Variant(ModifierSet<&'a str>, String, Option<&'a str>),
+    ModuleStart(&'a str, Meta<'a>),
+    ModuleEnd(&'a str),
+    Symbol(&'a str, Option<Value<'a>>, Meta<'a>),
+    Variant(ModifierSet<&'a str>, Value<'a>, Meta<'a>),
+}
+
+/// An error in a definition file.
+struct Error<'a> {
+    /// The erroneous part of the file. This is always a slice of the file's
+    /// text, so that its location can be determined.
+    span: &'a str,
+    /// A description of the error.
+    message: String,
+    /// A suggestion for how to fix the error.
+    hint: Option<&'static str>,
+}
+
+impl<'a> Error<'a> {
+    fn new(span: &'a str, message: impl Into<String>) -> Self {
+        Self { span, message: message.into(), hint: None }
+    }
+
+    fn with_hint(mut self, hint: &'static str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+}
+
+/// Entries of a table that is generated into the output, as tuples of the
+/// fully qualified path of a module or symbol, the modifiers, and a value.
+type Table = Vec<(String, String, String)>;
+
+/// An entry of the search index, see [`collect_search`].
+type SearchEntry = (String, String, String, Vec<String>, Option<String>);
+
+/// The output of the build script.
+#[derive(Default)]
+struct Output {
+    /// The generated Rust code.
+    buf: String,
+    /// The ambiguities found in all files.
+    ambiguities: Vec<Ambiguity>,
+    /// The aliases in all files, as tuples of the fully qualified path of the
+    /// symbol, the modifiers, and the canonical path of the target.
+    aliases: Table,
+    /// The descriptions in all files, as tuples of the fully qualified path of
+    /// the module or symbol, the modifiers, and the description.
+    descriptions: Table,
+    /// The speech texts in all files, as tuples of the fully qualified path of
+    /// the symbol, the modifiers, and the speech text.
+    speech: Table,
+    /// The variants in all files.
+    variants: Vec<Variant>,
+    /// The ASCII approximations in all files, as tuples of the value, the
+    /// approximation, and the fully qualified path of the variant.
+    approximations: Table,
+    /// The entries of the search index in all files.
+    search: Vec<SearchEntry>,
+    /// The distinct deprecation messages of the included definitions.
+    deprecations: BTreeSet<String>,
+    /// The names of the top-level modules that are included, alongside the
+    /// names of their constants.
+    modules: Vec<(String, String)>,
+    /// The names of the top-level modules whose files were skipped because
+    /// their feature is disabled.
+    skipped: Vec<String>,
+}
+
+/// A variant of a symbol, for the checks that concern all files.
+struct Variant {
+    /// The fully qualified path of the symbol.
+    path: String,
+    /// The modifiers, in the order of their definition.
+    modifiers: String,
+    /// The value.
+    value: String,
+    /// Whether the variant, its symbol, or a module around it is deprecated.
+    deprecated: bool,
+    /// Whether the variant is included in the output, i.e. whether all
+    /// required features are enabled.
+    included: bool,
+}
+
+impl Variant {
+    /// The fully qualified path of the variant, with the modifiers sorted
+    /// alphabetically.
+    fn key(&self) -> String {
+        key(&self.path, &self.modifiers)
+    }
+}
+
+/// The fully qualified path of a variant, with the modifiers sorted
+/// alphabetically.
+fn key(path: &str, modifiers: &str) -> String {
+    let mut list = iter::once(path)
+        .chain(ModifierSet::from_raw_dotted(modifiers))
+        .collect::<Vec<_>>();
+    list[1..].sort_unstable();
+    list.join(".")
+}
+
+/// A problem with the variants of a symbol that makes a variant unreachable or
+/// makes the result of a lookup depend on the order of the variants.
+struct Ambiguity {
+    /// The kind of problem: `duplicate`, `unreachable`, or `tie`.
+    kind: &'static str,
+    /// The fully qualified path of the affected variant or query, with the
+    /// modifiers in alphabetical order.
+    path: String,
+    /// A human-readable description of the problem.
+    message: String,
 }
 
 fn main() {
     println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-changed=Cargo.toml");
+    println!("cargo::rerun-if-env-changed=CODEX_FMT");
+    println!("cargo::rerun-if-env-changed=CODEX_IDS");
 
-    let mut buf = String::new();
-    process(&mut buf, Path::new("src/modules/sym.txt"), "SYM", "Named general symbols.");
-    process(&mut buf, Path::new("src/modules/emoji.txt"), "EMOJI", "Named emoji.");
+    let mut out = Output::default();
+    process(&mut out, Path::new("src/modules/sym.txt"), "SYM", "Named general symbols.");
+    process(&mut out, Path::new("src/modules/emoji.txt"), "EMOJI", "Named emoji.");
+    check_ambiguities(
+        &out.ambiguities,
+        &out.skipped,
+        Path::new("src/modules/ambiguities.txt"),
+    );
+    assign_ids(&mut out.buf, &out.variants, Path::new("src/modules/ids.txt"));
+    check_stability(
+        &out.variants,
+        &out.skipped,
+        Path::new("src/modules/release.txt"),
+        Path::new("CHANGELOG.md"),
+    );
 
-    let out = std::env::var_os("OUT_DIR").unwrap();
-    let dest = Path::new(&out).join("out.rs");
-    std::fs::write(&dest, buf).unwrap();
+    out.modules.sort();
+    out.buf.push_str("static ROOT_DEFS: [(&str, Binding); ");
+    write!(out.buf, "{}] = [", out.modules.len()).unwrap();
+    for (prefix, name) in &out.modules {
+        write!(out.buf, "({prefix:?}, Binding::new(Def::Module({name}))),").unwrap();
+    }
+    out.buf.push_str("];");
 
-    #[cfg(feature = "_test-unicode-conformance")]
-    {
-        let emoji_vs_list = Path::new(&out).join("emoji-variation-sequences.txt");
-        if !std::fs::read_to_string(&emoji_vs_list)
-            .is_ok_and(|text| text.contains("Version: 17.0"))
-        {
-            let content = ureq::get(
-                "https://www.unicode.org/Public/17.0.0/ucd/emoji/emoji-variation-sequences.txt",
-            )
-                .call()
-                .unwrap()
-                .body_mut()
-                .read_to_string()
-                .unwrap();
-            std::fs::write(emoji_vs_list, content).unwrap();
-        }
+    encode_names(&mut out.buf, &out.variants, &out.aliases);
+    encode_approximations(&mut out.buf, &mut out.approximations);
+    if is_enabled("html") {
+        encode_entities(
+            &mut out.buf,
+            &out.variants,
+            &out.aliases,
+            Path::new("src/modules/entities.json"),
+        );
     }
+    encode_table(&mut out.buf, "ALIASES", &mut out.aliases);
+    encode_table(&mut out.buf, "DESCRIPTIONS", &mut out.descriptions);
+    encode_table(&mut out.buf, "SPEECH", &mut out.speech);
+    encode_deprecations(&mut out.buf, &out.deprecations);
+    localize(&mut out.buf, &out.variants, &out.skipped, Path::new("src/modules/l10n"));
+    encode_search(&mut out.buf, &mut out.search);
+
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    let dest = Path::new(&out_dir).join("out.rs");
+    std::fs::write(&dest, out.buf).unwrap();
+
+    #[cfg(feature = "_test-unicode-conformance")]
+    provide_conformance_files(Path::new(&out_dir));
 }
 
 /// Processes a single file and turns it into a global module.
-fn process(buf: &mut String, file: &Path, name: &str, desc: &str) {
+fn process(out: &mut Output, file: &Path, name: &str, desc: &str) {
     println!("cargo::rerun-if-changed={}", file.display());
 
+    // Files whose feature is disabled aren't even parsed.
+    let prefix = name.to_lowercase();
+    if !is_enabled(&prefix) {
+        out.skipped.push(prefix);
+        return;
+    }
+
     let text = std::fs::read_to_string(file).unwrap();
-    let mut line_nr = 0;
-    let mut deprecation = None;
-    let mut iter = text
+    let (module, aliases) = match compile(&text, &prefix) {
+        Ok(compiled) => compiled,
+        Err(mut errors) => {
+            report(file, &text, &mut errors);
+            std::process::exit(1);
+        }
+    };
+
+    check_format(file, &text, &prefix, &snapshot(&module, &aliases, &prefix));
+
+    let mut errors = vec![];
+    check_features(&module, &declared_features(), &mut errors);
+    if !errors.is_empty() {
+        report(file, &text, &mut errors);
+        std::process::exit(1);
+    }
+
+    // The checks consider all definitions in the file, but only those whose
+    // features are enabled are included in the output.
+    lint(&module, &prefix, &mut out.ambiguities);
+    collect_variants(&module, &prefix, false, true, &mut out.variants);
+
+    let mut module = module;
+    exclude_disabled(&mut module);
+    out.aliases.extend(aliases.into_iter().filter(|(path, modifiers, _)| {
+        let path = path.strip_prefix(&format!("{prefix}.")).unwrap();
+        let path = if modifiers.is_empty() {
+            path.into()
+        } else {
+            format!("{path}.{modifiers}")
+        };
+        lookup(&module, &path).is_some()
+    }));
+    collect_descriptions(&module, &prefix, &mut out.descriptions);
+    collect_approximations(&module, &prefix, &mut out.approximations);
+    collect_speech(&module, &prefix, &mut out.speech);
+    collect_deprecations(&module, &mut out.deprecations);
+    collect_search(&module, &prefix, &[], None, &mut out.search);
+    out.modules.push((prefix.clone(), name.into()));
+
+    // The reference is copied out of the output directory by the `reference`
+    // test in `tests/generate.rs`.
+    let markdown = reference(&module, &prefix, Format::Markdown);
+    let dir = Path::new(&std::env::var_os("OUT_DIR").unwrap()).join("reference");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join(format!("{prefix}.md")),
+        format!("# `{prefix}`\n\n{desc}\n{markdown}"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join(format!("{prefix}.html")),
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{prefix}</title>\n</head>\n<body>\n<h1><code>{prefix}</code></h1>\n\
+             \n<p>{}</p>\n{}</body>\n</html>\n",
+            escape_html(desc),
+            reference(&module, &prefix, Format::Html),
+        ),
+    )
+    .unwrap();
+
+    let doc = if cfg!(feature = "reference-docs") {
+        format!("{desc}\n{markdown}")
+    } else {
+        desc.into()
+    };
+    // The definitions live in a static, so that every use of the constant
+    // refers to the same data and modules and symbols can be compared by
+    // identity.
+    let len = module.0.len();
+    write!(out.buf, "static {name}_DEFS: [(&str, Binding); {len}] = ").unwrap();
+    encode_defs(&mut out.buf, &module);
+    write!(
+        out.buf,
+        "; #[doc = {doc:?}] pub const {name}: Module = Module(&{name}_DEFS);"
+    )
+    .unwrap();
+}
+
+/// Parses the text of a definition file and resolves its aliases.
+fn compile<'a>(
+    text: &'a str,
+    prefix: &str,
+) -> Result<(Module<'a>, Table), Vec<Error<'a>>> {
+    let mut errors = vec![];
+    let declarations = declare(text, &mut errors);
+    let defs = parse(&mut declarations.into_iter().peekable(), None, &mut errors);
+    let mut module = Module::new(defs);
+
+    let mut aliases = vec![];
+    resolve_aliases(&mut module, prefix, &mut aliases, &mut errors);
+
+    if errors.is_empty() { Ok((module, aliases)) } else { Err(errors) }
+}
+
+/// Describes everything that is defined by a file, for checking that
+/// formatting doesn't change any definitions.
+fn snapshot(
+    module: &Module,
+    aliases: &[(String, String, String)],
+    prefix: &str,
+) -> String {
+    let mut buf = String::new();
+    encode(&mut buf, module);
+    let mut descriptions = vec![];
+    collect_descriptions(module, prefix, &mut descriptions);
+    let mut approximations = vec![];
+    collect_approximations(module, prefix, &mut approximations);
+    let mut speech = vec![];
+    collect_speech(module, prefix, &mut speech);
+    let mut search = vec![];
+    collect_search(module, prefix, &[], None, &mut search);
+    write!(buf, "{aliases:?}{descriptions:?}{approximations:?}{speech:?}{search:?}")
+        .unwrap();
+    buf
+}
+
+/// Checks that a file is in the canonical layout produced by [`format`].
+///
+/// The formatted file is always written to the output directory, from where
+/// the `format` test in `tests/generate.rs` copies it into the source tree. If
+/// the file isn't formatted and the `CODEX_FMT` environment variable is
+/// `check`, the build fails. Otherwise, a warning is emitted.
+fn check_format(file: &Path, text: &str, prefix: &str, expected: &str) {
+    let formatted = format(text);
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join(file.file_name().unwrap()), &formatted)
+        .unwrap();
+    if formatted == text {
+        return;
+    }
+
+    // Formatting must not change any definitions and must be idempotent.
+    let Ok((module, aliases)) = compile(&formatted, prefix) else {
+        panic!("formatting {} introduced errors", file.display());
+    };
+    assert_eq!(
+        snapshot(&module, &aliases, prefix),
+        expected,
+        "formatting {} changed its definitions",
+        file.display(),
+    );
+    assert_eq!(
+        format(&formatted),
+        formatted,
+        "formatting {} is unstable",
+        file.display()
+    );
+
+    let line = text
         .lines()
-        .inspect(|_| line_nr += 1)
-        .map(tokenize)
-        .chain(iter::once(Ok(Line::Eof)))
-        .filter_map(|line| match line {
-            Err(message) => Some(Err(message)),
-            Ok(Line::Blank) => None,
-            Ok(Line::Deprecated(message)) => {
-                if deprecation.is_some() {
-                    Some(Err(String::from("duplicate `@deprecated:`")))
+        .zip(formatted.lines())
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| text.lines().count().min(formatted.lines().count()))
+        + 1;
+
+    if std::env::var("CODEX_FMT").as_deref() == Ok("check") {
+        println!("cargo::warning={}:{line}: file is not formatted", file.display());
+        std::process::exit(1);
+    }
+    println!(
+        "cargo::warning={}:{line}: file is not formatted \
+         (run `cargo test --test generate -- --ignored format` to format it)",
+        file.display(),
+    );
+}
+
+/// Formats a definition file in the canonical layout, which must be free of
+/// errors.
+///
+/// In the canonical layout, the contents of modules and the variants of
+/// symbols are indented by two spaces, values are escaped as described in
+/// [`encode_value`], and there are no consecutive blank lines or blank lines
+/// at the start or end of a module. Comments, doc comments, and deprecations
+/// are kept and indented like the definition or variant that follows them.
+fn format(text: &str) -> String {
+    let mut f = Formatter { start: true, ..Formatter::default() };
+    let mut depth = 0;
+    for line in text.lines() {
+        let (content, doc) = split_doc(line);
+        let (content, comment) = match content.split_once("//") {
+            Some((head, tail)) => (head, Some(tail)),
+            None => (content, None),
+        };
+        let comment = match (doc, comment) {
+            (Some(doc), _) => Some(format_comment("///", doc)),
+            (None, Some(comment)) => Some(format_comment("//", comment)),
+            (None, None) => None,
+        };
+        let trailing = comment.as_ref().map(|c| format!(" {c}")).unwrap_or_default();
+
+        let indent = "  ".repeat(depth);
+        match tokenize(content).ok().expect("file should be free of errors") {
+            Line::Blank => f.pending.push(comment),
+            Line::Deprecated(message) => {
+                f.pending.push(Some(format!("@deprecated: {message}{trailing}")))
+            }
+            Line::Feature(name) => {
+                f.pending.push(Some(format!("@feature: {name}{trailing}")))
+            }
+            Line::Ascii(text) => {
+                f.pending.push(Some(format!("@ascii: {text}{trailing}")))
+            }
+            Line::Speech(text) => {
+                f.pending.push(Some(format!("@speech: {text}{trailing}")))
+            }
+            Line::Tags(tags) => f.pending.push(Some(format!("@tags: {tags}{trailing}"))),
+            Line::ModuleStart(name) => {
+                f.line(&indent, &format!("{name} {{{trailing}"));
+                f.start = true;
+                depth += 1;
+            }
+            Line::ModuleEnd(_) => {
+                depth -= 1;
+                f.flush(&indent);
+                f.blank = false;
+                f.push(&"  ".repeat(depth), &format!("}}{trailing}"));
+            }
+            Line::Symbol(name, value) => {
+                let value = value.map(|value| format!(" {}", format_value(&value)));
+                f.line(
+                    &indent,
+                    &format!("{name}{}{trailing}", value.unwrap_or_default()),
+                );
+            }
+            Line::Variant(modifiers, value) => {
+                let indent = format!("{indent}  ");
+                f.flush(&indent);
+                f.blank = false;
+                let value = format_value(&value);
+                f.push(&indent, &format!(".{} {value}{trailing}", modifiers.as_str()));
+            }
+        }
+    }
+    f.flush("");
+    f.out
+}
+
+/// Builds the output of [`format`] line by line.
+#[derive(Default)]
+struct Formatter {
+    /// The formatted text.
+    out: String,
+    /// Comments and deprecations that wait for the indentation of the next
+    /// definition or variant, interspersed with blank lines (`None`).
+    pending: Vec<Option<String>>,
+    /// Whether a blank line should precede the next line.
+    blank: bool,
+    /// Whether we are at the start of the file or a module.
+    start: bool,
+}
+
+impl Formatter {
+    /// Adds a line, preceded by the pending lines.
+    fn line(&mut self, indent: &str, text: &str) {
+        self.flush(indent);
+        self.push(indent, text);
+    }
+
+    /// Adds the pending lines.
+    fn flush(&mut self, indent: &str) {
+        for line in std::mem::take(&mut self.pending) {
+            match line {
+                Some(text) => self.push(indent, &text),
+                None => self.blank = true,
+            }
+        }
+    }
+
+    /// Adds a single line.
+    fn push(&mut self, indent: &str, text: &str) {
+        if std::mem::take(&mut self.blank) && !self.start {
+            self.out.push('\n');
+        }
+        self.start = false;
+        self.out.push_str(indent);
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+}
+
+/// Formats a comment with the given prefix, like `//` or `///`.
+fn format_comment(prefix: &str, text: &str) -> String {
+    let text = text.trim();
+    if text.is_empty() { prefix.into() } else { format!("{prefix} {text}") }
+}
+
+/// Formats the value of a symbol or variant.
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Literal(value) => encode_value(value),
+        Value::Alias(target, _) => format!("@alias {target}"),
+    }
+}
+
+/// Turns a decoded value back into its canonical source form.
+///
+/// Variation selectors are written as `\vs{...}` escapes, using `text` and
+/// `emoji` for VS15 and VS16. Characters that would be mistaken for syntax,
+/// that are invisible, or that are easily confused with other characters are
+/// written as `\u{...}` escapes with uppercase hexadecimal digits.
+fn encode_value(value: &str) -> String {
+    let mut buf = String::new();
+    let mut prev = None;
+    for c in value.chars() {
+        match c {
+            '\u{FE00}'..='\u{FE0D}' => {
+                write!(buf, "\\vs{{{}}}", c as u32 - 0xFE00 + 1).unwrap()
+            }
+            '\u{FE0E}' => buf.push_str("\\vs{text}"),
+            '\u{FE0F}' => buf.push_str("\\vs{emoji}"),
+            _ if needs_escape(c, prev) => write!(buf, "\\u{{{:X}}}", c as u32).unwrap(),
+            _ => buf.push(c),
+        }
+        prev = Some(c);
+    }
+    buf
+}
+
+/// Whether a character of a value is written as a `\u{...}` escape, given the
+/// preceding character.
+fn needs_escape(c: char, prev: Option<char>) -> bool {
+    // Characters that conflict with the syntax.
+    matches!(c, '\\' | '{' | '}')
+        || (c == '/' && prev == Some('/'))
+        // Characters that are hard to tell apart from similar ones.
+        || matches!(c, '-' | '.' | '\u{2011}')
+        || c.is_whitespace()
+        || c.is_control()
+        // Format characters and combining marks, unless they follow another
+        // character, like a zero-width joiner in an emoji sequence.
+        || (prev.is_none()
+            && matches!(
+                c,
+                '\u{AD}'
+                    | '\u{200B}'..='\u{200F}'
+                    | '\u{202A}'..='\u{202E}'
+                    | '\u{2060}'..='\u{2064}'
+                    | '\u{FEFF}'
+                    | '\u{300}'..='\u{36F}'
+                    | '\u{1AB0}'..='\u{1AFF}'
+                    | '\u{1DC0}'..='\u{1DFF}'
+                    | '\u{20D0}'..='\u{20FF}'
+                    | '\u{FE20}'..='\u{FE2F}'
+            ))
+}
+
+/// Turns the lines of a file into declarations, attaching metadata to the
+/// declarations that follow it. Erroneous lines are skipped.
+fn declare<'a>(text: &'a str, errors: &mut Vec<Error<'a>>) -> Vec<Declaration<'a>> {
+    let mut declarations = vec![];
+    let mut meta = Meta::default();
+    let mut section = None;
+    let mut depth = 0usize;
+    let mut after_blank = true;
+    for line in text.lines() {
+        // A comment that starts after a blank line at the top level, like
+        // `// Arrows.`, starts a new section.
+        if depth == 0
+            && after_blank
+            && let Some(comment) = line.trim_start().strip_prefix("//")
+            && !comment.starts_with('/')
+            && !comment.trim().is_empty()
+        {
+            let index = section.map_or(0, |(index, _)| index + 1);
+            section = Some((index, comment.trim().trim_end_matches('.')));
+        }
+        after_blank = line.trim().is_empty();
+
+        let (line, doc) = split_doc(line);
+        meta.doc.extend(doc);
+
+        let line = match tokenize(line) {
+            Ok(line) => line,
+            Err(error) => {
+                errors.push(error);
+                meta = Meta::default();
+                continue;
+            }
+        };
+
+        match line {
+            Line::Blank => {}
+            Line::Deprecated(message) => {
+                if meta.deprecation.is_some() {
+                    errors.push(Error::new(message, "duplicate `@deprecated:`"));
+                } else {
+                    meta.deprecation = Some(message);
+                }
+            }
+            Line::Feature(name) => {
+                if meta.feature.is_some() {
+                    errors.push(Error::new(name, "duplicate `@feature:`"));
+                } else {
+                    meta.feature = Some(name);
+                }
+            }
+            Line::Ascii(text) => {
+                if meta.ascii.is_some() {
+                    errors.push(Error::new(text, "duplicate `@ascii:`"));
                 } else {
-                    deprecation = Some(message);
-                    None
+                    meta.ascii = Some(text);
                 }
             }
-            Ok(Line::ModuleStart(name)) => {
-                Some(Ok(Declaration::ModuleStart(name, deprecation.take())))
+            Line::Speech(text) => {
+                if meta.speech.is_some() {
+                    errors.push(Error::new(text, "duplicate `@speech:`"));
+                } else {
+                    meta.speech = Some(text);
+                }
             }
-            Ok(Line::ModuleEnd) => {
-                if deprecation.is_some() {
-                    Some(Err(String::from("dangling `@deprecated:`")))
+            Line::Tags(tags) => {
+                if meta.tags.is_some() {
+                    errors.push(Error::new(tags, "duplicate `@tags:`"));
                 } else {
-                    Some(Ok(Declaration::ModuleEnd))
+                    meta.tags = Some(tags);
+                }
+            }
+            Line::ModuleStart(name) => {
+                if let Some(text) = meta.speech.take() {
+                    errors.push(
+                        Error::new(text, "`@speech:` on a module").with_hint(
+                            "only symbols and variants can have a speech text",
+                        ),
+                    );
+                }
+                if let Some(text) = meta.ascii.take() {
+                    errors.push(Error::new(text, "`@ascii:` on a module").with_hint(
+                        "only symbols and variants can have an approximation",
+                    ));
+                }
+                if depth == 0 {
+                    meta.section = section;
                 }
+                depth += 1;
+                declarations
+                    .push(Declaration::ModuleStart(name, std::mem::take(&mut meta)));
             }
-            Ok(Line::Symbol(name, value)) => {
-                Some(Ok(Declaration::Symbol(name, value, deprecation.take())))
+            Line::ModuleEnd(span) => {
+                depth = depth.saturating_sub(1);
+                check_dangling(&mut meta, errors);
+                declarations.push(Declaration::ModuleEnd(span));
             }
-            Ok(Line::Variant(modifiers, value)) => {
-                Some(Ok(Declaration::Variant(modifiers, value, deprecation.take())))
+            Line::Symbol(name, value) => {
+                if value.is_none()
+                    && let Some(text) = meta.ascii.take()
+                {
+                    errors.push(
+                        Error::new(text, "`@ascii:` on a symbol without a value")
+                            .with_hint("put it before the variant it approximates"),
+                    );
+                }
+                if depth == 0 {
+                    meta.section = section;
+                }
+                declarations.push(Declaration::Symbol(
+                    name,
+                    value,
+                    std::mem::take(&mut meta),
+                ));
             }
-            Ok(Line::Eof) => {
-                deprecation.map(|_| Err(String::from("dangling `@deprecated:`")))
+            Line::Variant(modifiers, value) => {
+                if let Some(name) = meta.feature.take() {
+                    errors.push(
+                        Error::new(name, "`@feature:` on a variant")
+                            .with_hint("only modules and symbols can require a feature"),
+                    );
+                }
+                declarations.push(Declaration::Variant(
+                    modifiers,
+                    value,
+                    std::mem::take(&mut meta),
+                ));
             }
-        })
-        .peekable();
+        }
+    }
+    check_dangling(&mut meta, errors);
+    declarations
+}
 
-    let module = match parse(&mut iter) {
-        Ok(defs) => Module::new(defs),
-        Err(e) => {
-            let message = format!("{}:{}: {e}", file.display(), line_nr);
-            println!("cargo::warning={message}");
-            std::process::exit(1);
+/// Reports metadata that isn't followed by a declaration and discards it.
+fn check_dangling<'a>(meta: &mut Meta<'a>, errors: &mut Vec<Error<'a>>) {
+    let meta = std::mem::take(meta);
+    if let Some(message) = meta.deprecation {
+        errors.push(
+            Error::new(message, "dangling `@deprecated:`")
+                .with_hint("`@deprecated:` must be followed by a definition or variant"),
+        );
+    } else if let Some(tags) = meta.tags {
+        errors.push(
+            Error::new(tags, "dangling `@tags:`")
+                .with_hint("`@tags:` must be followed by a definition or variant"),
+        );
+    } else if let Some(text) = meta.speech {
+        errors.push(
+            Error::new(text, "dangling `@speech:`")
+                .with_hint("`@speech:` must be followed by a symbol or variant"),
+        );
+    } else if let Some(text) = meta.ascii {
+        errors.push(
+            Error::new(text, "dangling `@ascii:`")
+                .with_hint("`@ascii:` must be followed by a symbol or variant"),
+        );
+    } else if let Some(name) = meta.feature {
+        errors.push(
+            Error::new(name, "dangling `@feature:`")
+                .with_hint("`@feature:` must be followed by a definition"),
+        );
+    } else if let Some(line) = meta.doc.first() {
+        errors.push(
+            Error::new(line, "dangling doc comment")
+                .with_hint("doc comments must be followed by a definition or variant"),
+        );
+    }
+}
+
+/// Prints errors as cargo warnings, alongside their location, the line they
+/// occur in, and a hint if there is one.
+fn report(file: &Path, text: &str, errors: &mut [Error]) {
+    errors.sort_by_key(|error| error.span.as_ptr());
+    for error in errors.iter() {
+        let offset = error.span.as_ptr() as usize - text.as_ptr() as usize;
+        let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+        let line = text[start..end].trim_end_matches('\r');
+        let line_nr = text[..offset].matches('\n').count() + 1;
+        let column = text[start..offset].chars().count() + 1;
+        let width = error
+            .span
+            .chars()
+            .count()
+            .min(line.chars().count().saturating_sub(column - 1))
+            .max(1);
+
+        println!(
+            "cargo::warning={}:{line_nr}:{column}: {}",
+            file.display(),
+            error.message
+        );
+        println!("cargo::warning=  | {line}");
+        println!("cargo::warning=  | {}{}", " ".repeat(column - 1), "^".repeat(width));
+        if let Some(hint) = error.hint {
+            println!("cargo::warning=  = hint: {hint}");
         }
-    };
+    }
+}
 
-    write!(buf, "#[doc = {desc:?}] pub const {name}: Module = ").unwrap();
-    encode(buf, &module);
-    buf.push(';');
+/// Splits off a `///` doc comment from a line, which may either stand on its
+/// own or follow a declaration.
+fn split_doc(line: &str) -> (&str, Option<&str>) {
+    match line.split_once("//") {
+        Some((head, tail)) => match tail.strip_prefix('/') {
+            Some(doc) => (head, Some(doc.trim())),
+            None => (line, None),
+        },
+        None => (line, None),
+    }
 }
 
 /// Tokenizes and classifies a line.
-fn tokenize(line: &str) -> StrResult<Line<'_>> {
+fn tokenize(line: &str) -> SourceResult<'_, Line<'_>> {
     // Strip comments.
     let line = line.split_once("//").map_or(line, |(head, _)| head);
 
@@ -163,55 +875,130 @@ fn tokenize(line: &str) -> StrResult<Line<'_>> {
     };
 
     Ok(if head == "@deprecated:" {
-        Line::Deprecated(tail.ok_or("missing deprecation message")?.trim())
+        let message = tail.map(str::trim).filter(|message| !message.is_empty());
+        Line::Deprecated(
+            message.ok_or_else(|| Error::new(head, "missing deprecation message"))?,
+        )
+    } else if head == "@feature:" {
+        let name = tail.map(str::trim).filter(|name| !name.is_empty());
+        Line::Feature(name.ok_or_else(|| Error::new(head, "missing feature name"))?)
+    } else if head == "@tags:" {
+        let tags = tail.map(str::trim).filter(|tags| !tags.is_empty());
+        let tags = tags.ok_or_else(|| Error::new(head, "missing tags"))?;
+        if tags.split(',').any(|tag| tag.trim().is_empty()) {
+            return Err(Error::new(tags, "empty tag"));
+        }
+        Line::Tags(tags)
+    } else if head == "@speech:" {
+        let text = tail.map(str::trim).filter(|text| !text.is_empty());
+        Line::Speech(text.ok_or_else(|| Error::new(head, "missing speech text"))?)
+    } else if head == "@ascii:" {
+        let text = tail.map(str::trim).filter(|text| !text.is_empty());
+        let text = text.ok_or_else(|| Error::new(head, "missing approximation"))?;
+        if !text.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+            return Err(Error::new(text, "approximation must be printable ASCII"));
+        }
+        Line::Ascii(text)
     } else if tail == Some("{") {
         validate_ident(head)?;
         Line::ModuleStart(head)
     } else if head == "}" && tail.is_none() {
-        Line::ModuleEnd
+        Line::ModuleEnd(head)
     } else if let Some(rest) = head.strip_prefix('.') {
         for part in rest.split('.') {
             validate_ident(part)?;
         }
-        let value = decode_value(tail.ok_or("missing char")?)?;
-        Line::Variant(ModifierSet::from_raw_dotted(rest), value)
+        if rest.split('.').count() > MAX_MODIFIERS {
+            return Err(Error::new(
+                rest,
+                format!("too many modifiers, a variant can have at most {MAX_MODIFIERS}"),
+            ));
+        }
+        let tail = tail.ok_or_else(|| Error::new(head, "missing char"))?;
+        Line::Variant(ModifierSet::from_raw_dotted(rest), parse_value(tail)?)
     } else {
         validate_ident(head)?;
-        let value = tail.map(decode_value).transpose()?;
+        let value = tail.map(parse_value).transpose()?;
         Line::Symbol(head, value)
     })
 }
 
+/// The maximum number of modifiers of a variant.
+///
+/// The lints go through all subsets of the modifiers of each variant, so
+/// their number must stay small, and in particular below the 32 bits of the
+/// mask that selects a subset.
+const MAX_MODIFIERS: usize = 16;
+
+/// Parses the value of a symbol or variant, which is either an `@alias` or a
+/// string with escapes.
+fn parse_value(text: &str) -> SourceResult<'_, Value<'_>> {
+    if text == "@alias" {
+        return Err(Error::new(text, "missing alias target"));
+    }
+    if let Some(target) = text.strip_prefix("@alias ") {
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(Error::new(text, "missing alias target"));
+        }
+        for part in target.split('.') {
+            validate_ident(part)?;
+        }
+        return Ok(Value::Alias(target, None));
+    }
+    if text.starts_with("@alias") {
+        return Err(Error::new(text, format!("malformed annotation: {text:?}"))
+            .with_hint("an alias is written as `@alias <path>`"));
+    }
+    decode_value(text).map(Value::Literal)
+}
+
 /// Ensures that a string is a valid identifier. In `codex`, we use very strict
 /// rules and allow only alphabetic ASCII chars.
-fn validate_ident(string: &str) -> StrResult<()> {
+fn validate_ident(string: &str) -> SourceResult<'_, ()> {
     if !string.is_empty() && string.chars().all(|c| c.is_ascii_alphabetic()) {
         return Ok(());
     }
-    Err(format!("invalid identifier: {string:?}"))
+    let error = Error::new(string, format!("invalid identifier: {string:?}"));
+    Err(if string.is_empty() {
+        error.with_hint("modifiers are separated by a single `.` and cannot be empty")
+    } else if string.chars().any(|c| c.is_ascii_digit()) {
+        error.with_hint(
+            "identifiers may only contain ASCII letters, so numbers must be spelled out, \
+             like in `dc.one`",
+        )
+    } else {
+        error.with_hint("identifiers may only contain ASCII letters")
+    })
 }
 
 /// Extracts the value of a variant, parsing `\u{XXXX}` and other escapes.
-fn decode_value(mut text: &str) -> StrResult<String> {
+fn decode_value(mut text: &str) -> SourceResult<'_, String> {
     let mut result = String::new();
     loop {
         if let Some(rest) = text.strip_prefix("\\u{") {
             let Some((code, tail)) = rest.split_once('}') else {
-                return Err(format!(
-                    "unclosed Unicode escape: \\u{{{}",
-                    rest.escape_debug()
+                return Err(Error::new(
+                    text,
+                    format!("unclosed Unicode escape: \\u{{{}", rest.escape_debug()),
                 ));
             };
             result.push(
                 u32::from_str_radix(code, 16)
                     .ok()
                     .and_then(|n| char::try_from(n).ok())
-                    .ok_or_else(|| format!("invalid Unicode escape \\u{{{code}}}"))?,
+                    .ok_or_else(|| {
+                        Error::new(code, format!("invalid Unicode escape \\u{{{code}}}"))
+                            .with_hint("expected the hexadecimal number of a codepoint")
+                    })?,
             );
             text = tail;
         } else if let Some(rest) = text.strip_prefix("\\vs{") {
             let Some((value, tail)) = rest.split_once('}') else {
-                return Err(format!("unclosed VS escape: \\vs{{{}", rest.escape_debug()));
+                return Err(Error::new(
+                    text,
+                    format!("unclosed VS escape: \\vs{{{}", rest.escape_debug()),
+                ));
             };
             let vs = match value {
                 "1" => '\u{fe00}',
@@ -230,13 +1017,24 @@ fn decode_value(mut text: &str) -> StrResult<String> {
                 "14" => '\u{fe0d}',
                 "15" | "text" => '\u{fe0e}',
                 "16" | "emoji" => '\u{fe0f}',
-                code => return Err(format!("invalid VS escape: \\vs{{{code}}}")),
+                code => {
+                    return Err(Error::new(
+                        code,
+                        format!("invalid VS escape: \\vs{{{code}}}"),
+                    )
+                    .with_hint("expected a number from 1 to 16, `text`, or `emoji`"));
+                }
             };
             result.push(vs);
             text = tail;
         } else if let Some((prefix, tail)) = text.find('\\').map(|i| text.split_at(i)) {
             if prefix.is_empty() {
-                return Err(format!("invalid escape sequence: {tail}"));
+                let end = tail.char_indices().nth(2).map_or(tail.len(), |(i, _)| i);
+                return Err(Error::new(
+                    &tail[..end],
+                    format!("invalid escape sequence: {tail}"),
+                )
+                .with_hint("the supported escapes are `\\u{...}` and `\\vs{...}`"));
             }
             result.push_str(prefix);
             text = tail;
@@ -247,74 +1045,1611 @@ fn decode_value(mut text: &str) -> StrResult<String> {
     }
 }
 
-/// Turns a stream of lines into a list of definitions.
+/// Turns a stream of declarations into a list of definitions.
+///
+/// `module` is the name of the module whose definitions are parsed, or `None`
+/// at the top level. Erroneous declarations are skipped.
 fn parse<'a>(
-    p: &mut Peekable<impl Iterator<Item = StrResult<Declaration<'a>>>>,
-) -> StrResult<Vec<(&'a str, Binding<'a>)>> {
+    p: &mut Peekable<impl Iterator<Item = Declaration<'a>>>,
+    module: Option<&'a str>,
+    errors: &mut Vec<Error<'a>>,
+) -> Vec<(&'a str, Binding<'a>)> {
     let mut defs = vec![];
     loop {
-        match p.next().transpose()? {
-            None | Some(Declaration::ModuleEnd) => {
+        match p.next() {
+            None => {
+                if let Some(name) = module {
+                    errors.push(
+                        Error::new(name, format!("unclosed module `{name}`"))
+                            .with_hint("modules must be closed with a `}`"),
+                    );
+                }
+                break;
+            }
+            Some(Declaration::ModuleEnd(span)) => {
+                if module.is_none() {
+                    errors.push(Error::new(span, "unexpected `}`"));
+                    continue;
+                }
                 break;
             }
-            Some(Declaration::Symbol(name, value, deprecation)) => {
+            Some(Declaration::Symbol(name, value, mut meta)) => {
                 let mut variants = vec![];
-                while let Some(Declaration::Variant(name, value, deprecation)) =
-                    p.peek().cloned().transpose()?
+                while let Some(Declaration::Variant(name, value, meta)) =
+                    p.peek().cloned()
                 {
-                    variants.push((name, value, deprecation));
+                    variants.push((name, value, meta));
                     p.next();
                 }
 
                 let symbol = if !variants.is_empty() {
                     if let Some(value) = value {
-                        variants.insert(0, (ModifierSet::default(), value, None));
+                        // The approximation belongs to the symbol's own value.
+                        let ascii = meta.ascii.take();
+                        let variant_meta = Meta { ascii, ..Meta::default() };
+                        variants.insert(0, (ModifierSet::default(), value, variant_meta));
                     }
                     Symbol::Multi(variants)
-                } else {
-                    let value = value.ok_or("symbol needs char or variants")?;
+                } else if let Some(value) = value {
                     Symbol::Single(value)
+                } else {
+                    errors.push(
+                        Error::new(name, "symbol needs char or variants").with_hint(
+                            "variants must be indented on the following lines",
+                        ),
+                    );
+                    continue;
                 };
 
-                defs.push((name, Binding { def: Def::Symbol(symbol), deprecation }));
+                defs.push((name, Binding { def: Def::Symbol(symbol), meta }));
             }
-            Some(Declaration::ModuleStart(name, deprecation)) => {
-                let module_defs = parse(p)?;
+            Some(Declaration::ModuleStart(name, meta)) => {
+                let module_defs = parse(p, Some(name), errors);
                 defs.push((
                     name,
-                    Binding {
-                        def: Def::Module(Module::new(module_defs)),
-                        deprecation,
-                    },
+                    Binding { def: Def::Module(Module::new(module_defs)), meta },
                 ));
             }
-            other => return Err(format!("expected definition, found {other:?}")),
+            Some(Declaration::Variant(modifiers, ..)) => {
+                errors.push(
+                    Error::new(modifiers.0, "expected definition, found variant")
+                        .with_hint("variants must directly follow their symbol"),
+                );
+            }
         }
     }
-    Ok(defs)
+
+    let mut seen = HashSet::new();
+    for &(name, _) in &defs {
+        if !seen.insert(name) {
+            errors.push(Error::new(name, format!("duplicate definition of `{name}`")));
+        }
+    }
+
+    defs
 }
 
-/// Encodes a `Module` into Rust code.
-fn encode(buf: &mut String, module: &Module) {
-    buf.push_str("Module(&[");
-    for (name, entry) in &module.0 {
-        write!(buf, "({name:?}, Binding {{ def: ").unwrap();
-        match &entry.def {
-            Def::Module(module) => {
-                buf.push_str("Def::Module(");
-                encode(buf, module);
-                buf.push(')');
+/// Resolves all aliases in a module to the values of their targets.
+///
+/// The targets must be fully specified, must not be deprecated, and must not
+/// be aliases themselves. Records each alias with its canonical target.
+fn resolve_aliases<'a>(
+    module: &mut Module<'a>,
+    prefix: &str,
+    aliases: &mut Table,
+    errors: &mut Vec<Error<'a>>,
+) {
+    fn collect<'a>(module: &Module<'a>, targets: &mut Vec<&'a str>) {
+        for (_, binding) in &module.0 {
+            match &binding.def {
+                Def::Module(module) => collect(module, targets),
+                Def::Symbol(Symbol::Single(value)) => targets.extend(value.target()),
+                Def::Symbol(Symbol::Multi(list)) => {
+                    targets.extend(list.iter().filter_map(|(_, value, _)| value.target()))
+                }
             }
-            Def::Symbol(symbol) => {
-                buf.push_str("Def::Symbol(Symbol::");
-                match symbol {
-                    Symbol::Single(value) => write!(buf, "Single({value:?})").unwrap(),
-                    Symbol::Multi(list) => write!(buf, "Multi(&{list:?})").unwrap(),
+        }
+    }
+
+    fn assign(
+        module: &mut Module,
+        path: &str,
+        resolved: &HashMap<&str, (String, String)>,
+        aliases: &mut Table,
+    ) {
+        for (name, binding) in &mut module.0 {
+            let path = format!("{path}.{name}");
+            let variants = match &mut binding.def {
+                Def::Module(module) => {
+                    assign(module, &path, resolved, aliases);
+                    continue;
+                }
+                Def::Symbol(Symbol::Single(value)) => {
+                    vec![(ModifierSet::default(), value)]
+                }
+                Def::Symbol(Symbol::Multi(list)) => list
+                    .iter_mut()
+                    .map(|(modifiers, value, _)| (*modifiers, value))
+                    .collect(),
+            };
+            for (modifiers, value) in variants {
+                if let Value::Alias(target, slot) = value
+                    && let Some((canonical, string)) = resolved.get(target)
+                {
+                    *slot = Some(string.clone());
+                    aliases.push((
+                        path.clone(),
+                        modifiers.as_str().into(),
+                        canonical.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut targets = vec![];
+    collect(module, &mut targets);
+
+    let mut resolved = HashMap::new();
+    for target in targets {
+        let Some((canonical, value, deprecation)) = lookup(module, target) else {
+            errors.push(
+                Error::new(target, format!("alias target `{target}` does not exist"))
+                    .with_hint(
+                        "the target must be a variant with exactly these modifiers",
+                    ),
+            );
+            continue;
+        };
+        if deprecation.is_some() {
+            errors.push(Error::new(
+                target,
+                format!("alias target `{target}` is deprecated"),
+            ));
+            continue;
+        }
+        let Value::Literal(value) = value else {
+            errors.push(Error::new(
+                target,
+                format!("alias target `{target}` is an alias itself"),
+            ));
+            continue;
+        };
+        resolved.insert(target, (format!("{prefix}.{canonical}"), value.clone()));
+    }
+
+    assign(module, prefix, &resolved, aliases);
+}
+
+/// Looks up the variant at a path relative to a module. The modifiers must
+/// match the variant exactly.
+///
+/// Returns the canonical path of the variant, with the modifiers in the order
+/// of their definition, alongside its value and deprecation.
+fn lookup<'m, 'a>(
+    module: &'m Module<'a>,
+    path: &str,
+) -> Option<(String, &'m Value<'a>, Option<&'a str>)> {
+    let (name, tail) = path.split_once('.').map_or((path, None), |(a, b)| (a, Some(b)));
+    let i = module.0.binary_search_by_key(&name, |&(k, _)| k).ok()?;
+    let binding = &module.0[i].1;
+    let (suffix, value, deprecation) = match &binding.def {
+        Def::Module(module) => lookup(module, tail?)?,
+        Def::Symbol(symbol) => {
+            let modifs = ModifierSet::from_raw_dotted(tail.unwrap_or_default());
+            let (modifiers, value, deprecation) = match symbol {
+                Symbol::Single(value) => {
+                    modifs.is_empty().then_some((ModifierSet::default(), value, None))?
+                }
+                Symbol::Multi(list) => {
+                    modifs
+                        .exact_match_in(list.iter().map(|(m, value, meta)| {
+                            (*m, (*m, value, meta.deprecation))
+                        }))?
+                }
+            };
+            (modifiers.as_str().to_string(), value, deprecation)
+        }
+    };
+    let canonical =
+        if suffix.is_empty() { name.into() } else { format!("{name}.{suffix}") };
+    Some((canonical, value, deprecation.or(binding.meta.deprecation)))
+}
+
+/// Collects the descriptions of all definitions and variants in a module.
+fn collect_descriptions(module: &Module, path: &str, descriptions: &mut Table) {
+    for (name, binding) in &module.0 {
+        let path = format!("{path}.{name}");
+        if let Some(description) = binding.meta.description() {
+            descriptions.push((path.clone(), String::new(), description));
+        }
+        match &binding.def {
+            Def::Module(module) => collect_descriptions(module, &path, descriptions),
+            Def::Symbol(Symbol::Single(_)) => {}
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (modifiers, _, meta) in list {
+                    if let Some(description) = meta.description() {
+                        descriptions.push((
+                            path.clone(),
+                            modifiers.as_str().into(),
+                            description,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Collects the ASCII approximations in a module, as tuples of the value, the
+/// approximation, and the fully qualified path of the variant.
+fn collect_approximations(module: &Module, path: &str, approximations: &mut Table) {
+    for (name, binding) in &module.0 {
+        let path = format!("{path}.{name}");
+        match &binding.def {
+            Def::Module(module) => collect_approximations(module, &path, approximations),
+            Def::Symbol(Symbol::Single(value)) => {
+                if let Some(ascii) = binding.meta.ascii {
+                    approximations.push((value.as_str().into(), ascii.into(), path));
+                }
+            }
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (modifiers, value, meta) in list {
+                    if let Some(ascii) = meta.ascii {
+                        let key = key(&path, modifiers.as_str());
+                        approximations.push((value.as_str().into(), ascii.into(), key));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Collects the speech texts in a module.
+///
+/// A variant's speech text is either given explicitly or derived from the
+/// speech text of its symbol and the words for its modifiers. It can't be
+/// derived if the symbol has no speech text or if a modifier has no words.
+fn collect_speech(module: &Module, path: &str, speech: &mut Table) {
+    for (name, binding) in &module.0 {
+        let path = format!("{path}.{name}");
+        let base = binding.meta.speech;
+        match &binding.def {
+            Def::Module(module) => collect_speech(module, &path, speech),
+            Def::Symbol(Symbol::Single(_)) => {
+                if let Some(text) = base {
+                    speech.push((path, String::new(), text.into()));
+                }
+            }
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (modifiers, _, meta) in list {
+                    let text = match meta.speech {
+                        Some(text) => Some(text.into()),
+                        None => base.and_then(|base| derive_speech(base, *modifiers)),
+                    };
+                    if let Some(text) = text {
+                        speech.push((path.clone(), modifiers.as_str().into(), text));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Collects the deprecation messages of all definitions in a module.
+fn collect_deprecations(module: &Module, deprecations: &mut BTreeSet<String>) {
+    for (_, binding) in &module.0 {
+        deprecations.extend(binding.meta.deprecation.map(String::from));
+        match &binding.def {
+            Def::Module(module) => collect_deprecations(module, deprecations),
+            Def::Symbol(Symbol::Single(_)) => {}
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (_, _, meta) in list {
+                    deprecations.extend(meta.deprecation.map(String::from));
+                }
+            }
+        }
+    }
+}
+
+/// Where the words for a modifier go in a derived speech text.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Place {
+    Negation,
+    Size,
+    Direction,
+    Style,
+    After,
+}
+
+/// The words for modifiers in derived speech texts.
+///
+/// The words are simply put around the speech text of the symbol, which
+/// doesn't work for every symbol: `eq.not` is "not equal to" rather than "not
+/// equals" and `in.rev` is "contains as member" rather than "reversed element
+/// of". Such variants have their own `@speech:` in the definition files.
+const SPOKEN_MODIFIERS: &[(&str, Place, &str)] = &[
+    ("not", Place::Negation, "not"),
+    ("long", Place::Size, "long"),
+    ("short", Place::Size, "short"),
+    ("big", Place::Size, "big"),
+    ("small", Place::Size, "small"),
+    ("tiny", Place::Size, "tiny"),
+    ("medium", Place::Size, "medium"),
+    ("l", Place::Direction, "left"),
+    ("r", Place::Direction, "right"),
+    ("t", Place::Direction, "up"),
+    ("b", Place::Direction, "down"),
+    ("tl", Place::Direction, "upper left"),
+    ("tr", Place::Direction, "upper right"),
+    ("bl", Place::Direction, "lower left"),
+    ("br", Place::Direction, "lower right"),
+    ("cw", Place::Direction, "clockwise"),
+    ("ccw", Place::Direction, "counterclockwise"),
+    ("double", Place::Style, "double"),
+    ("triple", Place::Style, "triple"),
+    ("quad", Place::Style, "quadruple"),
+    ("stroked", Place::Style, "white"),
+    ("filled", Place::Style, "black"),
+    ("dotted", Place::Style, "dotted"),
+    ("dashed", Place::Style, "dashed"),
+    ("light", Place::Style, "light"),
+    ("heavy", Place::Style, "heavy"),
+    ("o", Place::Style, "circled"),
+    ("sq", Place::Style, "square"),
+    ("inv", Place::Style, "inverted"),
+    ("rev", Place::Style, "reversed"),
+    ("twohead", Place::Style, "two-headed"),
+    ("hook", Place::Style, "hooked"),
+    ("squiggly", Place::Style, "squiggly"),
+    ("wave", Place::Style, "wave"),
+    ("bar", Place::After, "from bar"),
+    ("eq", Place::After, "or equal to"),
+    ("equiv", Place::After, "or equivalent to"),
+];
+
+/// Derives the speech text of a variant from the speech text of its symbol and
+/// the words for its modifiers, like `long right arrow` for `arrow.r.long`.
+fn derive_speech(base: &str, modifiers: ModifierSet<&str>) -> Option<String> {
+    let mut words = modifiers
+        .into_iter()
+        .map(|modifier| {
+            SPOKEN_MODIFIERS
+                .iter()
+                .find(|&&(m, ..)| m == modifier)
+                .map(|&(_, place, words)| (place, words))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    words.sort_by_key(|&(place, _)| place);
+    let after = words.partition_point(|&(place, _)| place < Place::After);
+    let before = words[..after].iter().map(|&(_, words)| words);
+    let rest = words[after..].iter().map(|&(_, words)| words);
+    Some(
+        before
+            .chain(iter::once(base))
+            .chain(rest)
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Collects all variants of all symbols in a module, which is itself
+/// deprecated if `deprecated` is `true` and included in the output if
+/// `included` is `true`.
+fn collect_variants(
+    module: &Module,
+    path: &str,
+    deprecated: bool,
+    included: bool,
+    variants: &mut Vec<Variant>,
+) {
+    for (name, binding) in &module.0 {
+        let path = format!("{path}.{name}");
+        let deprecated = deprecated || binding.meta.deprecation.is_some();
+        let included = included && binding.meta.feature.is_none_or(is_enabled);
+        match &binding.def {
+            Def::Module(module) => {
+                collect_variants(module, &path, deprecated, included, variants)
+            }
+            Def::Symbol(Symbol::Single(value)) => variants.push(Variant {
+                path,
+                modifiers: String::new(),
+                value: value.as_str().into(),
+                deprecated,
+                included,
+            }),
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (modifiers, value, meta) in list {
+                    variants.push(Variant {
+                        path: path.clone(),
+                        modifiers: modifiers.as_str().into(),
+                        value: value.as_str().into(),
+                        deprecated: deprecated || meta.deprecation.is_some(),
+                        included,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Whether a fully qualified path is in a top-level module whose file was
+/// skipped.
+fn is_skipped(skipped: &[String], path: &str) -> bool {
+    let module = path.split('.').next().unwrap_or(path);
+    skipped.iter().any(|name| name == module)
+}
+
+/// Whether a cargo feature is enabled.
+fn is_enabled(feature: &str) -> bool {
+    let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+    std::env::var_os(var).is_some()
+}
+
+/// The names of the features that are declared in `Cargo.toml`.
+fn declared_features() -> HashSet<String> {
+    let manifest = std::fs::read_to_string("Cargo.toml").unwrap();
+    let section = manifest.split_once("[features]").map_or("", |(_, tail)| tail);
+    section
+        .lines()
+        .take_while(|line| !line.starts_with('['))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, _)| name.trim().into())
+        .collect()
+}
+
+/// Reports the features required by definitions in a module that aren't
+/// declared.
+fn check_features<'a>(
+    module: &Module<'a>,
+    declared: &HashSet<String>,
+    errors: &mut Vec<Error<'a>>,
+) {
+    for (_, binding) in &module.0 {
+        if let Some(feature) = binding.meta.feature
+            && !declared.contains(feature)
+        {
+            errors.push(
+                Error::new(feature, format!("unknown feature `{feature}`"))
+                    .with_hint("features must be declared in Cargo.toml"),
+            );
+        }
+        if let Def::Module(module) = &binding.def {
+            check_features(module, declared, errors);
+        }
+    }
+}
+
+/// Removes the definitions whose required feature isn't enabled.
+fn exclude_disabled(module: &mut Module) {
+    module
+        .0
+        .retain(|(_, binding)| binding.meta.feature.is_none_or(is_enabled));
+    for (_, binding) in &mut module.0 {
+        if let Def::Module(module) = &mut binding.def {
+            exclude_disabled(module);
+        }
+    }
+}
+
+/// Finds ambiguities in the variants of all symbols in a module.
+fn lint(module: &Module, path: &str, ambiguities: &mut Vec<Ambiguity>) {
+    for (name, binding) in &module.0 {
+        let path = format!("{path}.{name}");
+        match &binding.def {
+            Def::Module(module) => lint(module, &path, ambiguities),
+            Def::Symbol(Symbol::Single(_)) => {}
+            Def::Symbol(Symbol::Multi(variants)) => {
+                let variants = variants
+                    .iter()
+                    .map(|(modifiers, value, _)| (*modifiers, value.as_str()))
+                    .collect::<Vec<_>>();
+                lint_symbol(&variants, &path, ambiguities)
+            }
+        }
+    }
+}
+
+/// Finds ambiguities in the variants of a single symbol.
+///
+/// Only the queries which are subsets of at least one variant are relevant, as
+/// all others don't match anything. Ties for the empty query are fine: A symbol
+/// without a default variant is expected to fall back to its first variant.
+fn lint_symbol<'a>(
+    variants: &[(ModifierSet<&'a str>, &str)],
+    path: &str,
+    ambiguities: &mut Vec<Ambiguity>,
+) {
+    let sorted = |set: ModifierSet<&'a str>| {
+        let mut list = set.into_iter().collect::<Vec<_>>();
+        list.sort_unstable();
+        list
+    };
+    let qualify = |list: &[&str]| {
+        iter::once(path)
+            .chain(list.iter().copied())
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let display = |set: ModifierSet<&str>| {
+        let list = set.into_iter().collect::<Vec<_>>();
+        format!("`{}`", qualify(&list))
+    };
+
+    let mut duplicate = vec![false; variants.len()];
+    for (i, &(set, ..)) in variants.iter().enumerate() {
+        let key = sorted(set);
+        if let Some(&(first, ..)) =
+            variants[..i].iter().find(|&&(other, ..)| sorted(other) == key)
+        {
+            duplicate[i] = true;
+            ambiguities.push(Ambiguity {
+                kind: "duplicate",
+                path: qualify(&key),
+                message: format!(
+                    "{} has the same modifiers as {}",
+                    display(set),
+                    display(first),
+                ),
+            });
+        }
+    }
+
+    let mut queries = BTreeSet::new();
+    for &(set, ..) in variants {
+        // The parser rejects variants with more than `MAX_MODIFIERS`
+        // modifiers, so the shift can't overflow.
+        let list = sorted(set);
+        for mask in 0..1u32 << list.len() {
+            queries.insert(
+                list.iter()
+                    .enumerate()
+                    .filter(|&(j, _)| mask & (1 << j) != 0)
+                    .map(|(_, m)| *m)
+                    .collect::<Vec<_>>(),
+            );
+        }
+    }
+
+    let mut reachable = vec![false; variants.len()];
+    for query in queries {
+        let joined = query.join(".");
+        let modifs = ModifierSet::from_raw_dotted(joined.as_str());
+        let scores = variants
+            .iter()
+            .map(|&(set, ..)| modifs.match_score(set))
+            .collect::<Vec<_>>();
+        let Some(best) = scores.iter().flatten().max() else { continue };
+        let winners = (0..variants.len())
+            .filter(|&i| scores[i].as_ref() == Some(best))
+            .collect::<Vec<_>>();
+
+        reachable[winners[0]] = true;
+        if !query.is_empty()
+            && winners.iter().any(|&i| variants[i].1 != variants[winners[0]].1)
+        {
+            let list = winners
+                .iter()
+                .map(|&i| display(variants[i].0))
+                .collect::<Vec<_>>()
+                .join(", ");
+            ambiguities.push(Ambiguity {
+                kind: "tie",
+                path: qualify(&query),
+                message: format!(
+                    "`{}` matches {list} equally well, the first one wins",
+                    qualify(&query),
+                ),
+            });
+        }
+    }
+
+    for (i, &(set, ..)) in variants.iter().enumerate() {
+        // Duplicates are never reachable and were already reported.
+        if !reachable[i] && !duplicate[i] {
+            ambiguities.push(Ambiguity {
+                kind: "unreachable",
+                path: qualify(&sorted(set)),
+                message: format!("{} can never be selected", display(set)),
+            });
+        }
+    }
+}
+
+/// Compares the found ambiguities with the allow-list and fails the build if
+/// there are any that are not allowed or if the allow-list is outdated.
+///
+/// Each line of the allow-list has the form `<kind> <path>`, just like the
+/// `path` and `kind` of an [`Ambiguity`].
+fn check_ambiguities(ambiguities: &[Ambiguity], skipped: &[String], file: &Path) {
+    println!("cargo::rerun-if-changed={}", file.display());
+
+    let text = std::fs::read_to_string(file).unwrap();
+    let mut allowed = BTreeSet::new();
+    for line in text.lines() {
+        let line = line.split_once("//").map_or(line, |(head, _)| head).trim();
+        let path = line.split_once(' ').map_or(line, |(_, path)| path);
+        if !line.is_empty() && !is_skipped(skipped, path) {
+            allowed.insert(line);
+        }
+    }
+
+    let mut failed = false;
+    let mut used = BTreeSet::new();
+    for Ambiguity { kind, path, message } in ambiguities {
+        let entry = format!("{kind} {path}");
+        if let Some(&line) = allowed.get(entry.as_str()) {
+            used.insert(line);
+        } else {
+            println!(
+                "cargo::warning={message} (add `{entry}` to {} if this is intended)",
+                file.display(),
+            );
+            failed = true;
+        }
+    }
+
+    for line in allowed.difference(&used) {
+        println!("cargo::warning={}: outdated entry `{line}`", file.display());
+        failed = true;
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// The format of the reference.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Format {
+    Markdown,
+    Html,
+}
+
+/// Writes a reference of all definitions in a file, grouped by section.
+fn reference(module: &Module, prefix: &str, format: Format) -> String {
+    let mut buf = String::new();
+    let section = |binding: &Binding| binding.meta.section.map(|(index, _)| index);
+    let mut defs = module.0.iter().collect::<Vec<_>>();
+    defs.sort_by_key(|(_, binding)| section(binding));
+    for group in defs.chunk_by(|(_, a), (_, b)| section(a) == section(b)) {
+        if let Some((_, section)) = group[0].1.meta.section {
+            match format {
+                Format::Markdown => write!(buf, "\n## {section}\n").unwrap(),
+                Format::Html => {
+                    write!(buf, "\n<h2>{}</h2>\n", escape_html(section)).unwrap()
+                }
+            }
+        }
+        document(&mut buf, group, prefix, 3, format);
+    }
+    buf
+}
+
+/// Writes a table of the symbols among some definitions, followed by a
+/// subsection for each of the modules among them.
+fn document(
+    buf: &mut String,
+    defs: &[&(&str, Binding)],
+    path: &str,
+    level: usize,
+    format: Format,
+) {
+    let mut rows = String::new();
+    for (name, binding) in defs {
+        let path = format!("{path}.{name}");
+        let deprecation = binding.meta.deprecation;
+        match &binding.def {
+            Def::Module(_) => {}
+            Def::Symbol(Symbol::Single(value)) => {
+                row(&mut rows, &path, value, &binding.meta, deprecation, format)
+            }
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (i, (modifiers, value, meta)) in list.iter().enumerate() {
+                    // The symbol's own description belongs to its first row.
+                    let meta =
+                        if i == 0 && meta.doc.is_empty() { &binding.meta } else { meta };
+                    let path = if modifiers.is_empty() {
+                        path.clone()
+                    } else {
+                        format!("{path}.{}", modifiers.as_str())
+                    };
+                    let deprecation = meta.deprecation.or(deprecation);
+                    row(&mut rows, &path, value, meta, deprecation, format);
+                }
+            }
+        }
+    }
+    if !rows.is_empty() {
+        match format {
+            Format::Markdown => {
+                buf.push_str("\n| Name | Symbol | Codepoints | Notes |\n");
+                buf.push_str("| ---- | ------ | ---------- | ----- |\n");
+                buf.push_str(&rows);
+            }
+            Format::Html => {
+                buf.push_str("\n<table>\n<thead><tr><th>Name</th><th>Symbol</th>");
+                buf.push_str("<th>Codepoints</th><th>Notes</th></tr></thead>\n<tbody>\n");
+                buf.push_str(&rows);
+                buf.push_str("</tbody>\n</table>\n");
+            }
+        }
+    }
+
+    for (name, binding) in defs {
+        let Def::Module(module) = &binding.def else { continue };
+        let path = format!("{path}.{name}");
+        let level = level.min(6);
+        match format {
+            Format::Markdown => write!(buf, "\n{} `{path}`\n", "#".repeat(level)),
+            Format::Html => write!(buf, "\n<h{level}><code>{path}</code></h{level}>\n"),
+        }
+        .unwrap();
+        if let Some(description) = binding.meta.description() {
+            match format {
+                Format::Markdown => write!(buf, "\n{description}\n"),
+                Format::Html => {
+                    write!(buf, "\n<p>{}</p>\n", markdown_to_html(&description))
+                }
+            }
+            .unwrap();
+        }
+        if let Some(message) = binding.meta.deprecation {
+            match format {
+                Format::Markdown => write!(buf, "\n*Deprecated:* {message}\n"),
+                Format::Html => write!(
+                    buf,
+                    "\n<p><em>Deprecated:</em> {}</p>\n",
+                    markdown_to_html(message)
+                ),
+            }
+            .unwrap();
+        }
+        let defs = module.0.iter().collect::<Vec<_>>();
+        document(buf, &defs, &path, level + 1, format);
+    }
+}
+
+/// Writes a row of the reference table for a symbol or variant.
+fn row(
+    buf: &mut String,
+    path: &str,
+    value: &Value,
+    meta: &Meta,
+    deprecation: Option<&str>,
+    format: Format,
+) {
+    let string = value.as_str();
+    let symbol = string.chars().filter(|c| !c.is_control()).collect::<String>();
+    let codepoints = string
+        .chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut notes = vec![];
+    notes.extend(meta.description());
+    if let Some(target) = value.target() {
+        let prefix = path.split_once('.').map_or(path, |(prefix, _)| prefix);
+        notes.push(format!("Same as `{prefix}.{target}`."));
+    }
+    if let Some(message) = deprecation {
+        notes.push(format!("*Deprecated:* {message}"));
+    }
+
+    match format {
+        Format::Markdown => writeln!(
+            buf,
+            "| `{path}` | {} | {codepoints} | {} |",
+            escape_markdown(&symbol),
+            notes.join("<br>").replace('|', "\\|").replace('\n', " "),
+        ),
+        Format::Html => writeln!(
+            buf,
+            "<tr id=\"{path}\"><td><code>{path}</code></td><td>{}</td>\
+             <td>{codepoints}</td><td>{}</td></tr>",
+            escape_html(&symbol),
+            notes
+                .iter()
+                .map(|note| markdown_to_html(note))
+                .collect::<Vec<_>>()
+                .join("<br>"),
+        ),
+    }
+    .unwrap();
+}
+
+/// Escapes the characters that have a meaning in Markdown, so that a value is
+/// displayed literally, even within a table. Descriptions and deprecation
+/// messages, on the other hand, may use Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut buf = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '&' | '~' => {
+                buf.push('\\');
+                buf.push(c);
+            }
+            _ => buf.push(c),
+        }
+    }
+    buf
+}
+
+/// Escapes the characters that have a meaning in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    let mut buf = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            _ => buf.push(c),
+        }
+    }
+    buf
+}
+
+/// Turns the Markdown in descriptions, deprecation messages, and notes into
+/// HTML. Only code spans and emphasis at the start of a note, like in
+/// `*Deprecated:*`, are used there.
+fn markdown_to_html(text: &str) -> String {
+    let (emphasis, text) = match text.strip_prefix('*').and_then(|t| t.split_once('*')) {
+        Some((emphasis, rest)) => (format!("<em>{}</em>", escape_html(emphasis)), rest),
+        None => (String::new(), text),
+    };
+    let code = text.split('`').enumerate().map(|(i, part)| {
+        if i % 2 == 1 {
+            format!("<code>{}</code>", escape_html(part))
+        } else {
+            escape_html(part)
+        }
+    });
+    iter::once(emphasis).chain(code).collect()
+}
+
+/// Looks up the stable IDs of all variants in the ID manifest and encodes
+/// tables for going from a variant to its ID and back.
+///
+/// Each line of the manifest has the form `<id> <path>`, where the modifiers in
+/// the path are sorted alphabetically. Lines are never removed, so that the IDs
+/// of removed variants aren't reused. Variants without an ID are assigned new
+/// ones in a copy of the manifest in the output directory, from where the `ids`
+/// test in `tests/generate.rs` copies it into the source tree. They fail the
+/// build if the `CODEX_IDS` environment variable is `check` and emit a warning
+/// otherwise.
+fn assign_ids(buf: &mut String, variants: &[Variant], file: &Path) {
+    println!("cargo::rerun-if-changed={}", file.display());
+
+    let mut text = std::fs::read_to_string(file).unwrap();
+    let mut ids = HashMap::new();
+    let mut next = 1;
+    let mut failed = false;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split_once("//").map_or(line, |(head, _)| head).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((id, path)) = line.split_once(' ').and_then(|(id, path)| {
+            Some((id.parse::<u32>().ok().filter(|&id| id > 0)?, path.trim()))
+        }) else {
+            println!(
+                "cargo::warning={}:{}: expected `<id> <path>`",
+                file.display(),
+                i + 1
+            );
+            failed = true;
+            continue;
+        };
+        if ids.insert(path.to_string(), id).is_some() || id < next {
+            println!(
+                "cargo::warning={}:{}: IDs must be unique and increasing",
+                file.display(),
+                i + 1
+            );
+            failed = true;
+        }
+        next = next.max(id + 1);
+    }
+
+    let check = std::env::var("CODEX_IDS").as_deref() == Ok("check");
+    let mut table = vec![];
+    for variant in variants {
+        let key = variant.key();
+        let id = match ids.get(&key) {
+            Some(&id) => id,
+            None => {
+                if check {
+                    println!("cargo::warning=`{key}` has no ID in {}", file.display());
+                    failed = true;
+                } else {
+                    println!(
+                        "cargo::warning=`{key}` has no ID in {} \
+                         (run `cargo test --test generate -- --ignored ids` to assign one)",
+                        file.display(),
+                    );
+                }
+                writeln!(text, "{next} {key}").unwrap();
+                ids.insert(key, next);
+                next += 1;
+                next - 1
+            }
+        };
+        if variant.included {
+            table.push((id, &variant.path, &variant.modifiers, &variant.value));
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("ids.txt"), text).unwrap();
+
+    table.sort_by_key(|&(_, path, modifiers, _)| (path, modifiers));
+    buf.push_str("const VARIANT_IDS: &[(&str, ModifierSet<&str>, u32)] = &[");
+    for (id, path, modifiers, _) in &table {
+        write!(buf, "({path:?}, ModifierSet({modifiers:?}), {id}),").unwrap();
+    }
+    buf.push_str("];");
+
+    table.sort_by_key(|&(id, ..)| id);
+    buf.push_str("const VARIANTS_BY_ID: &[(u32, &str, ModifierSet<&str>, &str)] = &[");
+    for (id, path, modifiers, value) in &table {
+        write!(buf, "({id}, {path:?}, ModifierSet({modifiers:?}), {value:?}),").unwrap();
+    }
+    buf.push_str("];");
+}
+
+/// Compares the variants with the manifest of the last release and fails the
+/// build if a variant was removed without being deprecated in the last release
+/// or if its value changed without being listed in the changelog.
+///
+/// Each line of the manifest has the form `<path> <value>`, followed by
+/// `@deprecated` for deprecated variants. The modifiers in the path are sorted
+/// alphabetically and the value is escaped like in the definition files. A
+/// manifest of the current variants is written to the output directory, from
+/// where the `release` test in `tests/generate.rs` copies it into the source
+/// tree for every release.
+fn check_stability(
+    variants: &[Variant],
+    skipped: &[String],
+    file: &Path,
+    changelog: &Path,
+) {
+    println!("cargo::rerun-if-changed={}", file.display());
+    println!("cargo::rerun-if-changed={}", changelog.display());
+
+    let text = std::fs::read_to_string(file).unwrap();
+    let current = variants.iter().map(|v| (v.key(), v)).collect::<HashMap<_, _>>();
+
+    let mut buf = String::new();
+    for line in text.lines().take_while(|l| l.is_empty() || l.starts_with("//")) {
+        writeln!(buf, "{line}").unwrap();
+    }
+    let mut keys = current.keys().collect::<Vec<_>>();
+    keys.sort_unstable();
+    for key in keys {
+        let variant = current[key];
+        write!(buf, "{key} {}", encode_value(&variant.value)).unwrap();
+        buf.push_str(if variant.deprecated { " @deprecated\n" } else { "\n" });
+    }
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("release.txt"), buf).unwrap();
+
+    let changed = changed_values(&std::fs::read_to_string(changelog).unwrap());
+    let mut failed = false;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split_once("//").map_or(line, |(head, _)| head);
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        let (path, value, deprecated) = match parts.as_slice() {
+            [] => continue,
+            [path, value] => (*path, *value, false),
+            [path, value, "@deprecated"] => (*path, *value, true),
+            _ => {
+                println!(
+                    "cargo::warning={}:{}: expected `<path> <value>`, \
+                     optionally followed by `@deprecated`",
+                    file.display(),
+                    i + 1,
+                );
+                failed = true;
+                continue;
+            }
+        };
+        if is_skipped(skipped, path) {
+            continue;
+        }
+        let value = match decode_value(value) {
+            Ok(value) => value,
+            Err(error) => {
+                println!(
+                    "cargo::warning={}:{}: {}",
+                    file.display(),
+                    i + 1,
+                    error.message
+                );
+                failed = true;
+                continue;
+            }
+        };
+
+        match current.get(path) {
+            None if !deprecated => {
+                println!(
+                    "cargo::warning=`{path}` was removed without being deprecated \
+                     in the last release (deprecate it with `@deprecated:` instead)",
+                );
+                failed = true;
+            }
+            Some(variant)
+                if variant.value != value
+                    && !changed.iter().any(|name| mentions(name, variant)) =>
+            {
+                println!(
+                    "cargo::warning=the value of `{path}` changed from {value:?} to {:?} \
+                     (list it under \"Changed values\" in {} if this is intended)",
+                    variant.value,
+                    changelog.display(),
+                );
+                failed = true;
+            }
+            _ => {}
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Collects the names that are listed under a ``Changed values in `<module>` ``
+/// heading in the unreleased section of the changelog, qualified with the
+/// module.
+fn changed_values(changelog: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut module = None;
+    let unreleased = changelog.split_once("## Unreleased").map_or("", |(_, tail)| tail);
+    for line in unreleased.lines() {
+        if line.starts_with("## Version") {
+            break;
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            module = heading
+                .strip_prefix("Changed values in `")
+                .and_then(|tail| tail.split_once('`'))
+                .map(|(module, _)| module);
+        } else if let Some(module) = module
+            && line.trim_start().starts_with("- ")
+        {
+            names.extend(
+                line.split('`')
+                    .skip(1)
+                    .step_by(2)
+                    .map(|name| format!("{module}.{name}")),
+            );
+        }
+    }
+    names
+}
+
+/// Whether a name from the changelog refers to a variant. A name ending in `.*`
+/// refers to all variants that have the modifiers before it.
+fn mentions(name: &str, variant: &Variant) -> bool {
+    let Some(tail) = name.strip_prefix(variant.path.as_str()) else { return false };
+    let (tail, wildcard) = match tail.strip_suffix("*") {
+        Some(tail) => (tail.strip_suffix('.').unwrap_or(tail), true),
+        None => (tail, false),
+    };
+    let tail = match tail.strip_prefix('.') {
+        Some(tail) => tail,
+        None if tail.is_empty() => tail,
+        None => return false,
+    };
+    let named = ModifierSet::from_raw_dotted(tail);
+    let actual = ModifierSet::from_raw_dotted(variant.modifiers.as_str());
+    named.is_subset(actual) && (wildcard || actual.is_subset(named))
+}
+
+/// Encodes a table of string triples keyed by the path of a module or symbol
+/// and a set of modifiers into Rust code, sorted for binary search.
+fn encode_table(buf: &mut String, name: &str, table: &mut [(String, String, String)]) {
+    table.sort();
+    write!(buf, "const {name}: &[(&str, ModifierSet<&str>, &str)] = &[").unwrap();
+    for (path, modifiers, value) in table.iter() {
+        write!(buf, "({path:?}, ModifierSet({modifiers:?}), {value:?}),").unwrap();
+    }
+    buf.push_str("];");
+}
+
+/// Encodes the distinct deprecation messages, sorted for binary search.
+///
+/// The message at index `i` gets a `Deprecation<{i + 1}>` whose `check`
+/// function is deprecated with that message, so that the `sym!` and `emoji!`
+/// macros can emit the real message as a warning. Attributes only take
+/// literals, so this can't be done with a single generic function.
+fn encode_deprecations(buf: &mut String, deprecations: &BTreeSet<String>) {
+    write!(buf, "static DEPRECATIONS: [&str; {}] = [", deprecations.len()).unwrap();
+    for message in deprecations {
+        write!(buf, "{message:?},").unwrap();
+    }
+    buf.push_str("];");
+    for (i, message) in deprecations.iter().enumerate() {
+        write!(
+            buf,
+            "impl macros::Deprecation<{}> {{ #[deprecated = {message:?}] pub const fn check() {{}} }}",
+            i + 1,
+        )
+        .unwrap();
+    }
+}
+
+/// Encodes a `Module` into Rust code.
+fn encode(buf: &mut String, module: &Module) {
+    buf.push_str("Module(&");
+    encode_defs(buf, module);
+    buf.push(')');
+}
+
+/// Encodes the preferred name of each non-ASCII value, sorted by value.
+///
+/// Deprecated variants are never preferred. Among the others, variants that
+/// aren't aliases are preferred, and then the shortest name wins. The length
+/// of the longest value in bytes is encoded as well, which bounds the search
+/// for the longest match when transpiling.
+fn encode_names(buf: &mut String, variants: &[Variant], aliases: &Table) {
+    let aliases = aliases
+        .iter()
+        .map(|(path, modifiers, _)| key(path, modifiers))
+        .collect::<HashSet<_>>();
+    let mut names = BTreeMap::<&str, (bool, usize, String, &Variant)>::new();
+    for variant in variants {
+        if !variant.included || variant.deprecated || variant.value.is_ascii() {
+            continue;
+        }
+        let key = variant.key();
+        let rank = (aliases.contains(&key), key.len(), key, variant);
+        let best = names.entry(&variant.value).or_insert_with(|| rank.clone());
+        if (rank.0, rank.1, &rank.2) < (best.0, best.1, &best.2) {
+            *best = rank;
+        }
+    }
+
+    buf.push_str("const NAMES: &[(&str, &str, ModifierSet<&str>)] = &[");
+    for (value, (.., variant)) in &names {
+        let (path, modifiers) = (&variant.path, &variant.modifiers);
+        write!(buf, "({value:?}, {path:?}, ModifierSet({modifiers:?})),").unwrap();
+    }
+    buf.push_str("];");
+
+    let longest = names.keys().map(|value| value.len()).max().unwrap_or(0);
+    write!(buf, "const LONGEST_NAMED_VALUE: usize = {longest};").unwrap();
+}
+
+/// Encodes the ASCII approximation of each value, sorted by value.
+///
+/// Variants with the same value, like aliases, need not all have an
+/// approximation, but those that have one must agree.
+fn encode_approximations(buf: &mut String, approximations: &mut Table) {
+    approximations.sort();
+    let mut failed = false;
+    for pair in approximations.windows(2) {
+        let [(value, a, first), (other, b, second)] = pair else { unreachable!() };
+        if value == other && a != b {
+            println!(
+                "cargo::warning=`{first}` and `{second}` have the same value, \
+                 but different approximations `{a}` and `{b}`"
+            );
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+
+    buf.push_str("const APPROXIMATIONS: &[(&str, &str)] = &[");
+    let mut last = None;
+    for (value, ascii, _) in approximations.iter() {
+        if last != Some(value) {
+            write!(buf, "({value:?}, {ascii:?}),").unwrap();
+            last = Some(value);
+        }
+    }
+    buf.push_str("];");
+}
+
+/// Encodes the HTML named character references from the WHATWG list, which
+/// has one entry per line.
+///
+/// Emits the entities sorted by name, the preferred entity of each variant
+/// that has one, the preferred entity of each value, sorted by value, and the
+/// preferred path of each preferred entity, sorted by entity. The
+/// names are given without `&` and `;`, and the legacy names without `;` are
+/// skipped. The shortest name is preferred, and then a lowercase one.
+fn encode_entities(buf: &mut String, variants: &[Variant], aliases: &Table, file: &Path) {
+    println!("cargo::rerun-if-changed={}", file.display());
+    let text = std::fs::read_to_string(file).unwrap();
+    let mut entities = vec![];
+    let mut failed = false;
+    for (i, line) in text.lines().enumerate() {
+        let Some((name, rest)) =
+            line.trim().strip_prefix("\"&").and_then(|l| l.split_once("\":"))
+        else {
+            continue;
+        };
+        let Some(name) = name.strip_suffix(';') else { continue };
+        let value = rest
+            .split_once('[')
+            .and_then(|(_, rest)| rest.split_once(']'))
+            .and_then(|(codepoints, _)| {
+                codepoints
+                    .split(',')
+                    .map(|c| char::from_u32(c.trim().parse().ok()?))
+                    .collect::<Option<String>>()
+            });
+        let Some(value) = value else {
+            println!(
+                "cargo::warning={}:{}: expected the codepoints of `{name}` as a list \
+                 of decimal numbers",
+                file.display(),
+                i + 1,
+            );
+            failed = true;
+            continue;
+        };
+        entities.push((name, value));
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    entities.sort();
+
+    let mut preferred = BTreeMap::<&str, &str>::new();
+    for (name, value) in &entities {
+        let rank =
+            |name: &str| (name.len(), name.to_lowercase() != name, name.to_owned());
+        let best = preferred.entry(value).or_insert(name);
+        if rank(name) < rank(best) {
+            *best = name;
+        }
+    }
+
+    buf.push_str("const ENTITIES: &[(&str, &str)] = &[");
+    for (name, value) in &entities {
+        write!(buf, "({name:?}, {value:?}),").unwrap();
+    }
+    buf.push_str("];");
+
+    let mut table = variants
+        .iter()
+        .filter(|variant| variant.included)
+        .filter_map(|variant| {
+            let entity = preferred.get(variant.value.as_str())?;
+            Some((variant.path.clone(), variant.modifiers.clone(), entity.to_string()))
+        })
+        .collect::<Table>();
+    encode_table(buf, "VARIANT_ENTITIES", &mut table);
+
+    buf.push_str("const ENTITY_BY_VALUE: &[(&str, &str)] = &[");
+    for (value, name) in &preferred {
+        write!(buf, "({value:?}, {name:?}),").unwrap();
+    }
+    buf.push_str("];");
+
+    // The path of a preferred reference is that of a variant which maps back
+    // to it, preferring one whose name and modifiers occur in the reference,
+    // like `sym.plus.o` for `oplus`, and otherwise ranked like in `NAMES`.
+    let aliases = aliases
+        .iter()
+        .map(|(path, modifiers, _)| key(path, modifiers))
+        .collect::<HashSet<_>>();
+    let mut paths = BTreeMap::<&str, (usize, bool, usize, String, &Variant)>::new();
+    for variant in variants {
+        if !variant.included || variant.deprecated || variant.value.is_ascii() {
+            continue;
+        }
+        let Some(&entity) = preferred.get(variant.value.as_str()) else { continue };
+        let lowercase = entity.to_lowercase();
+        let name = variant.path.rsplit('.').next().unwrap();
+        let missing = iter::once(name)
+            .chain(ModifierSet::from_raw_dotted(variant.modifiers.as_str()))
+            .filter(|part| !lowercase.contains(part))
+            .count();
+        let key = variant.key();
+        let rank = (missing, aliases.contains(&key), key.len(), key, variant);
+        let best = paths.entry(entity).or_insert_with(|| rank.clone());
+        if (rank.0, rank.1, rank.2, &rank.3) < (best.0, best.1, best.2, &best.3) {
+            *best = rank;
+        }
+    }
+
+    buf.push_str("const ENTITY_PATHS: &[(&str, &str, ModifierSet<&str>)] = &[");
+    for (entity, (.., variant)) in &paths {
+        let (path, modifiers) = (&variant.path, &variant.modifiers);
+        write!(buf, "({entity:?}, {path:?}, ModifierSet({modifiers:?})),").unwrap();
+    }
+    buf.push_str("];");
+}
+
+/// Reads the translations in a directory, with one file per locale, and
+/// checks that they only refer to existing modules, symbols, and variants.
+/// Translations of deprecated names are stale and produce a warning.
+///
+/// If the `l10n` feature is enabled, the translations are encoded as a table
+/// per locale, sorted by path.
+fn localize(buf: &mut String, variants: &[Variant], skipped: &[String], dir: &Path) {
+    println!("cargo::rerun-if-changed={}", dir.display());
+    let symbols = variants.iter().map(|v| v.path.as_str()).collect::<HashSet<_>>();
+    let modules = symbols
+        .iter()
+        .flat_map(|path| path.match_indices('.').map(|(i, _)| &path[..i]))
+        .collect::<HashSet<_>>();
+    let keys = variants.iter().map(Variant::key).collect::<HashSet<_>>();
+
+    // Splits a path into the path of a module or symbol and the modifiers.
+    let split = |path: &str| -> Option<(String, String)> {
+        if modules.contains(path) || symbols.contains(path) {
+            return Some((path.into(), String::new()));
+        }
+        let (prefix, modifiers) = path
+            .match_indices('.')
+            .map(|(i, _)| (&path[..i], &path[i + 1..]))
+            .find(|(prefix, _)| symbols.contains(prefix))?;
+        keys.contains(&key(prefix, modifiers))
+            .then(|| (prefix.into(), modifiers.into()))
+    };
+
+    // Whether all variants of a module, symbol, or variant are deprecated.
+    let deprecated = |path: &str, modifiers: &str| {
+        let key = key(path, modifiers);
+        let mut matching = variants.iter().filter(|variant| {
+            if modifiers.is_empty() {
+                variant.path == path || variant.path.starts_with(&format!("{path}."))
+            } else {
+                variant.key() == key
+            }
+        });
+        matching.all(|variant| variant.deprecated)
+    };
+
+    let mut files = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut failed = false;
+    let mut locales = vec![];
+    for file in &files {
+        let locale = file.file_stem().unwrap().to_str().unwrap().to_owned();
+        let text = std::fs::read_to_string(file).unwrap();
+        let mut seen = HashSet::new();
+        let mut entries = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let location = format!("{}:{}", file.display(), i + 1);
+            let Some((path, rest)) = line.split_once(" = ") else {
+                println!("cargo::warning={location}: expected `<path> = <description>`");
+                failed = true;
+                continue;
+            };
+            if is_skipped(skipped, path) {
+                continue;
+            }
+            let (description, keywords) = rest.split_once(" | ").unwrap_or((rest, ""));
+            let Some((path, modifiers)) = split(path) else {
+                println!("cargo::warning={location}: unknown name `{path}`");
+                failed = true;
+                continue;
+            };
+            if !seen.insert(key(&path, &modifiers)) {
+                println!("cargo::warning={location}: duplicate entry for `{path}`");
+                failed = true;
+            }
+            if deprecated(&path, &modifiers) {
+                let name = if modifiers.is_empty() {
+                    path.clone()
+                } else {
+                    format!("{path}.{modifiers}")
+                };
+                println!(
+                    "cargo::warning={location}: stale translation for deprecated `{name}`"
+                );
+            }
+            let keywords = keywords
+                .split(',')
+                .map(str::trim)
+                .filter(|keyword| !keyword.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>();
+            entries.push((path, modifiers, description.trim().to_owned(), keywords));
+        }
+        entries.sort();
+        locales.push((locale, entries));
+    }
+    locales.sort();
+    if failed {
+        std::process::exit(1);
+    }
+
+    if !is_enabled("l10n") {
+        return;
+    }
+    buf.push_str("const LOCALES: &[(&str, l10n::Table)] = &[");
+    for (locale, entries) in &locales {
+        write!(buf, "({locale:?}, &[").unwrap();
+        for (path, modifiers, description, keywords) in entries {
+            write!(
+                buf,
+                "({path:?}, ModifierSet({modifiers:?}), ({description:?}, &{keywords:?})),"
+            )
+            .unwrap();
+        }
+        buf.push_str("]),");
+    }
+    buf.push_str("];");
+}
+
+/// Collects the entries for the search index from a module, as tuples of the
+/// fully qualified path of the symbol, the modifiers, the value, the tags,
+/// and the section.
+///
+/// Tags of modules and symbols apply to everything inside of them. Deprecated
+/// modules, symbols, and variants are skipped.
+fn collect_search(
+    module: &Module,
+    path: &str,
+    tags: &[String],
+    section: Option<&str>,
+    entries: &mut Vec<SearchEntry>,
+) {
+    for (name, binding) in &module.0 {
+        if binding.meta.deprecation.is_some() {
+            continue;
+        }
+        let path = format!("{path}.{name}");
+        let section = binding.meta.section.map(|(_, title)| title).or(section);
+        let tags = tags.iter().cloned().chain(binding.meta.tags()).collect::<Vec<_>>();
+        match &binding.def {
+            Def::Module(module) => collect_search(module, &path, &tags, section, entries),
+            Def::Symbol(Symbol::Single(value)) => {
+                let section = section.map(String::from);
+                entries.push((path, String::new(), value.as_str().into(), tags, section));
+            }
+            Def::Symbol(Symbol::Multi(list)) => {
+                for (modifiers, value, meta) in list {
+                    if meta.deprecation.is_some() {
+                        continue;
+                    }
+                    let tags = tags.iter().cloned().chain(meta.tags()).collect();
+                    let (modifiers, value) =
+                        (modifiers.as_str().into(), value.as_str().into());
+                    let section = section.map(String::from);
+                    entries.push((path.clone(), modifiers, value, tags, section));
+                }
+            }
+        }
+    }
+}
+
+/// Encodes the search index, sorted by path.
+fn encode_search(buf: &mut String, entries: &mut [SearchEntry]) {
+    entries.sort();
+    buf.push_str("const SEARCH: &[search::Entry] = &[");
+    for (path, modifiers, value, tags, section) in entries.iter() {
+        write!(
+            buf,
+            "search::Entry {{ path: {path:?}, modifiers: ModifierSet({modifiers:?}), \
+             value: {value:?}, tags: &{tags:?}, section: {section:?} }},"
+        )
+        .unwrap();
+    }
+    buf.push_str("];");
+}
+
+/// The version of the Unicode Character Database that the conformance tests
+/// expect.
+#[cfg(feature = "_test-unicode-conformance")]
+const UCD_VERSION: &str = "17.0.0";
+
+/// Whether a file of the Unicode Character Database is of the version in
+/// [`UCD_VERSION`], judging by the comments at its start. Files without such
+/// comments, like `UnicodeData.txt`, are always accepted.
+#[cfg(feature = "_test-unicode-conformance")]
+fn has_ucd_version(text: &str) -> bool {
+    let mut header = text.lines().take_while(|line| line.starts_with('#')).peekable();
+    let version = UCD_VERSION.strip_suffix(".0").unwrap();
+    header.peek().is_none() || header.any(|line| line.contains(version))
+}
+
+/// Provides the files of the Unicode Character Database that the conformance
+/// tests need in the output directory.
+///
+/// The files are read by their name alone from the directory in the
+/// `CODEX_UCD_DIR` environment variable, so that the build needs no network
+/// access, and must be of the version in [`UCD_VERSION`].
+#[cfg(feature = "_test-unicode-conformance")]
+fn provide_conformance_files(out_dir: &Path) {
+    println!("cargo::rerun-if-env-changed=CODEX_UCD_DIR");
+    let Some(dir) = std::env::var_os("CODEX_UCD_DIR") else {
+        println!(
+            "cargo::warning=the conformance tests need `CODEX_UCD_DIR` to point to a \
+             directory with the files of Unicode {UCD_VERSION}"
+        );
+        std::process::exit(1);
+    };
+    for name in ["emoji-variation-sequences.txt", "UnicodeData.txt", "emoji-test.txt"] {
+        let file = Path::new(&dir).join(name);
+        println!("cargo::rerun-if-changed={}", file.display());
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            println!(
+                "cargo::warning={}: missing file of the Unicode Character Database",
+                file.display(),
+            );
+            std::process::exit(1);
+        };
+        if !has_ucd_version(&text) {
+            println!(
+                "cargo::warning={}: expected a file of Unicode {UCD_VERSION}",
+                file.display(),
+            );
+            std::process::exit(1);
+        }
+        std::fs::write(out_dir.join(name), text).unwrap();
+    }
+}
+
+/// Encodes the definitions of a `Module` into a Rust array.
+fn encode_defs(buf: &mut String, module: &Module) {
+    buf.push('[');
+    for (name, entry) in &module.0 {
+        write!(buf, "({name:?}, Binding {{ def: ").unwrap();
+        match &entry.def {
+            Def::Module(module) => {
+                buf.push_str("Def::Module(");
+                encode(buf, module);
+                buf.push(')');
+            }
+            Def::Symbol(symbol) => {
+                // The data of each symbol lives in its own static, so that
+                // symbols with the same data, like `sym.gradient` and
+                // `sym.nabla`, still differ by identity.
+                buf.push_str("Def::Symbol(Symbol::");
+                match symbol {
+                    Symbol::Single(value) => {
+                        let bytes = value.as_str().as_bytes();
+                        write!(
+                            buf,
+                            "Single({{ static VALUE: [u8; {}] = {bytes:?}; \
+                             static_str(&VALUE) }})",
+                            bytes.len(),
+                        )
+                        .unwrap()
+                    }
+                    Symbol::Multi(list) => {
+                        let list = list
+                            .iter()
+                            .map(|(modifiers, value, meta)| {
+                                (modifiers, value.as_str(), meta.deprecation)
+                            })
+                            .collect::<Vec<_>>();
+                        write!(
+                            buf,
+                            "Multi({{ static LIST: [(ModifierSet<&str>, &str, \
+                             Option<&str>); {}] = {list:?}; &LIST }})",
+                            list.len(),
+                        )
+                        .unwrap()
+                    }
                 }
                 buf.push(')');
             }
         }
-        write!(buf, ", deprecation: {:?} }}),", entry.deprecation).unwrap();
+        write!(buf, ", deprecation: {:?} }}),", entry.meta.deprecation).unwrap();
     }
-    buf.push_str("])");
+    buf.push(']');
 }
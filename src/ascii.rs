@@ -0,0 +1,71 @@
+//! Approximate symbols with ASCII text.
+//!
+//! Some variants have an ASCII approximation, like `->` for `→` or `inf` for
+//! `∞`, for contexts that can't display Unicode, like plain-text exports or
+//! some terminals.
+//!
+//! ```
+//! # #[cfg(feature = "sym")] {
+//! assert_eq!(codex::ascii::approximate("≤"), Some("<="));
+//! assert_eq!(codex::ascii::replace("x ≤ ∞ → y"), "x <= inf -> y");
+//! # }
+//! ```
+
+use crate::APPROXIMATIONS;
+
+/// Get the ASCII approximation of a value, if it has one.
+pub fn approximate(value: &str) -> Option<&'static str> {
+    APPROXIMATIONS
+        .binary_search_by_key(&value, |&(value, _)| value)
+        .ok()
+        .map(|i| APPROXIMATIONS[i].1)
+}
+
+/// Replace all values in a text that have an ASCII approximation with it.
+///
+/// At each position, the longest value with an approximation is replaced.
+/// Everything else, including non-ASCII characters without an approximation,
+/// is kept as it is.
+pub fn replace(text: &str) -> String {
+    let longest = APPROXIMATIONS.iter().map(|(value, _)| value.len()).max().unwrap_or(0);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let found = (1..=longest.min(rest.len()))
+            .rev()
+            .filter(|&len| rest.is_char_boundary(len))
+            .find_map(|len| approximate(&rest[..len]).map(|ascii| (len, ascii)));
+        match found {
+            Some((len, ascii)) => {
+                out.push_str(ascii);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+#[cfg(all(test, feature = "sym"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approximations_are_ascii() {
+        for &(value, ascii) in APPROXIMATIONS {
+            assert!(!ascii.is_empty(), "{value}");
+            assert!(ascii.chars().all(|c| c.is_ascii_graphic() || c == ' '), "{value}");
+        }
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(super::replace("⟦x⟧"), "[[x]]");
+        assert_eq!(super::replace("a ⟹ b"), "a ==> b");
+        assert_eq!(super::replace("2 × 3 ≠ 5 ✓"), "2 x 3 != 5 ✓");
+        assert_eq!(super::replace("↔\u{fe0e}"), "<->");
+    }
+}
@@ -0,0 +1,163 @@
+//! Map between symbols and HTML named character references.
+//!
+//! The references, like `&rarr;`, are taken from the [list of the WHATWG
+//! HTML standard][list]. Their names are given without the leading `&` and
+//! the trailing `;`. When a value has multiple references, like `rarr` and
+//! `rightarrow` for `→`, the shortest one is preferred, and then a lowercase
+//! one.
+//!
+//! ```
+//! # #[cfg(feature = "sym")] {
+//! use codex::html;
+//!
+//! assert_eq!(html::entity("sym.arrow.r"), Some("rarr"));
+//! assert_eq!(html::decode("Longrightarrow"), Some("⟹"));
+//! assert_eq!(html::escape("a → b & c"), "a &rarr; b &amp; c");
+//! # }
+//! ```
+//!
+//! [list]: https://html.spec.whatwg.org/multipage/named-characters.html
+
+use crate::{ENTITIES, ENTITY_BY_VALUE, ENTITY_PATHS, ModifierSet, VARIANT_ENTITIES};
+
+/// Get the value of a named character reference, like `→` for `rarr`.
+pub fn decode(name: &str) -> Option<&'static str> {
+    ENTITIES
+        .binary_search_by_key(&name, |&(name, _)| name)
+        .ok()
+        .map(|i| ENTITIES[i].1)
+}
+
+/// Get the preferred name of the variant for a named character reference.
+///
+/// Returns the fully qualified path of the symbol and the modifiers. If the
+/// reference is the one [`entity`] prefers for its value, the variant maps
+/// back to it, like `sym.plus.o` for `oplus`. Otherwise, the name is the one
+/// given by [`transpile::name`](crate::transpile::name).
+pub fn path(name: &str) -> Option<(&'static str, ModifierSet<&'static str>)> {
+    match ENTITY_PATHS.binary_search_by_key(&name, |&(name, _, _)| name) {
+        Ok(i) => Some((ENTITY_PATHS[i].1, ENTITY_PATHS[i].2)),
+        Err(_) => crate::transpile::name(decode(name)?),
+    }
+}
+
+/// Get the preferred named character reference for a variant.
+///
+/// Given a fully qualified path like `sym.arrow.r`, this returns the name of
+/// a reference with the same value, like `rarr`. The modifiers in `path` may
+/// be in any order, but must match the variant exactly.
+pub fn entity(path: &str) -> Option<&'static str> {
+    crate::lookup_table(VARIANT_ENTITIES, path)
+}
+
+/// Escape a text for HTML, using named character references where available.
+///
+/// The characters `&`, `<`, `>`, and `"` are always escaped. Other ASCII
+/// characters are kept as they are. Everything else is replaced with the
+/// preferred reference of the longest value that has one at each position.
+pub fn escape(text: &str) -> String {
+    let longest = ENTITY_BY_VALUE
+        .iter()
+        .map(|(value, _)| value.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let found = (1..=longest.min(rest.len()))
+            .rev()
+            .filter(|&len| rest.is_char_boundary(len))
+            .filter(|&len| !rest[..len].is_ascii() || matches!(c, '&' | '<' | '>' | '"'))
+            .find_map(|len| preferred(&rest[..len]).map(|name| (len, name)));
+        match found {
+            Some((len, name)) => {
+                out.push('&');
+                out.push_str(name);
+                out.push(';');
+                rest = &rest[len..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+/// Get the preferred named character reference for a value.
+fn preferred(value: &str) -> Option<&'static str> {
+    ENTITY_BY_VALUE
+        .binary_search_by_key(&value, |&(value, _)| value)
+        .ok()
+        .map(|i| ENTITY_BY_VALUE[i].1)
+}
+
+#[cfg(all(test, feature = "sym"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups() {
+        assert_eq!(decode("rarr"), Some("→"));
+        assert_eq!(decode("rarr;"), None);
+        assert_eq!(entity("sym.arrow.double.r.long"), Some("xrArr"));
+        assert_eq!(entity("sym.subset.not"), Some("nsub"));
+        let (path, modifiers) = path("nsub").unwrap();
+        assert_eq!((path, modifiers.as_str()), ("sym.subset", "not"));
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(super::escape(r#"<a href="x">"#), "&lt;a href=&quot;x&quot;&gt;");
+        assert_eq!(
+            super::escape("x ≤ y ⇒ f(x) ≤ f(y)"),
+            "x &le; y &rArr; f(x) &le; f(y)"
+        );
+        assert_eq!(super::escape("café"), "caf&eacute;");
+    }
+
+    #[test]
+    fn unnamed_entities() {
+        let unnamed = ENTITIES
+            .iter()
+            .filter(|&&(_, value)| !value.is_ascii())
+            .filter(|&&(_, value)| crate::transpile::name(value).is_none())
+            .map(|&(name, _)| name)
+            .collect::<Vec<_>>();
+        let expected = include_str!("modules/unnamed-entities.txt")
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .collect::<Vec<_>>();
+        assert_eq!(unnamed, expected);
+    }
+
+    #[test]
+    fn references() {
+        assert_eq!(ENTITIES.len(), 2125);
+        assert_eq!(decode("AElig"), Some("Æ"));
+        assert_eq!(decode("fjlig"), Some("fj"));
+        assert_eq!(decode("NotEqualTilde"), Some("\u{2242}\u{338}"));
+        assert_eq!(decode("nvlt"), Some("<\u{20D2}"));
+        assert_eq!(entity("sym.arrow.l.r.double"), Some("iff"));
+        assert_eq!(entity("sym.eq.not"), Some("ne"));
+        assert_eq!(entity("sym.exists.not"), Some("nexist"));
+        assert_eq!(entity("sym.in.rev"), Some("ni"));
+        assert_eq!(entity("sym.plus.o"), Some("oplus"));
+        assert_eq!(entity("sym.dagger.double"), Some("Dagger"));
+        assert_eq!(entity("sym.arrow.foo"), None);
+        let (path, modifiers) = path("oplus").unwrap();
+        assert_eq!((path, modifiers.as_str()), ("sym.plus", "o"));
+        assert_eq!(entity("sym.plus.o"), Some("oplus"));
+        let (path, modifiers) = super::path("CirclePlus").unwrap();
+        assert_eq!((path, modifiers.as_str()), ("sym.xor", ""));
+        assert_eq!(super::path("AElig"), None);
+        for &(name, path, modifiers) in ENTITY_PATHS {
+            let full = match modifiers.as_str() {
+                "" => path.to_owned(),
+                modifiers => format!("{path}.{modifiers}"),
+            };
+            assert_eq!(entity(&full), Some(name));
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Translated descriptions and search keywords.
+//!
+//! Translations are available for a few locales, like `de`, `ja`, and `pt`,
+//! each of which covers some modules, symbols, and variants. When a locale
+//! has no entry for a name, the lookup falls back to the locale's parents,
+//! e.g. from `pt-BR` to `pt`, and then to the English
+//! [`description`](crate::description).
+//!
+//! ```
+//! # #[cfg(feature = "sym")] {
+//! use codex::l10n;
+//!
+//! assert_eq!(l10n::description("de", "sym.arrow.r"), Some("Pfeil nach rechts"));
+//! assert_eq!(l10n::description("pt-BR", "sym.arrow.r"), Some("seta para a direita"));
+//! assert_eq!(l10n::description("ja", "sym.arrow.r"), Some("右向き矢印"));
+//! assert!(l10n::keywords("de", "sym.arrow.r.double").contains(&"Implikation"));
+//! # }
+//! ```
+
+use crate::{LOCALES, ModifierSet, lookup_table};
+
+/// Iterate over the locales that have translations, in alphabetical order.
+pub fn locales() -> impl Iterator<Item = &'static str> {
+    LOCALES.iter().map(|&(locale, _)| locale)
+}
+
+/// Get the translated description of a module, symbol, or variant.
+///
+/// Given a locale like `de` and a fully qualified path like `sym.arrow.r`,
+/// this returns the description in the locale or, if it has none, in one of
+/// its fallbacks. The modifiers in `path` may be in any order, but must match
+/// the variant exactly.
+pub fn description(locale: &str, path: &str) -> Option<&'static str> {
+    fallbacks(locale)
+        .find_map(|table| lookup_table(table, path))
+        .map(|(description, _)| description)
+        .or_else(|| crate::description(path))
+}
+
+/// Get the search keywords for a module, symbol, or variant in a locale.
+///
+/// Like [`description`], this falls back to the locale's parents if it has no
+/// entry for the path. Returns an empty list if there are no keywords.
+pub fn keywords(locale: &str, path: &str) -> &'static [&'static str] {
+    fallbacks(locale)
+        .find_map(|table| lookup_table(table, path))
+        .map_or(&[], |(_, keywords)| keywords)
+}
+
+/// The translation tables for a locale and its parents, from the most to the
+/// least specific.
+///
+/// The locale is a BCP 47 language tag like `pt-BR`, whose parents are
+/// obtained by removing subtags from the end. Tags are compared
+/// case-insensitively and `_` is treated like `-`.
+fn fallbacks(locale: &str) -> impl Iterator<Item = Table> {
+    let locale = locale.replace('_', "-");
+    let mut tag = Some(locale.as_str());
+    let mut tags = vec![];
+    while let Some(current) = tag {
+        tags.push(current.to_owned());
+        tag = current.rsplit_once('-').map(|(parent, _)| parent);
+    }
+    tags.into_iter().filter_map(|tag| {
+        LOCALES
+            .iter()
+            .find(|&&(locale, _)| locale.eq_ignore_ascii_case(&tag))
+            .map(|&(_, table)| table)
+    })
+}
+
+/// The translations of a locale, sorted by path.
+pub(crate) type Table = &'static [(&'static str, ModifierSet<&'static str>, Entry)];
+
+/// A translation, as a tuple of the description and the keywords.
+pub(crate) type Entry = (&'static str, &'static [&'static str]);
+
+#[cfg(all(test, feature = "sym"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallbacks() {
+        assert_eq!(description("pt-BR", "sym.forall"), Some("quantificador universal"));
+        assert_eq!(keywords("pt_br", "sym.forall"), ["para todo", "qualquer que seja"]);
+        assert_eq!(keywords("pt-BR", "sym.exists"), ["existe"]);
+        assert_eq!(
+            description("DE", "sym.arrow.double.r"),
+            Some("Doppelpfeil nach rechts")
+        );
+        assert_eq!(description("fr", "sym.control"), crate::description("sym.control"));
+        assert_eq!(keywords("fr", "sym.arrow.r"), [] as [&str; 0]);
+    }
+
+    #[test]
+    fn locales() {
+        assert_eq!(super::locales().collect::<Vec<_>>(), ["de", "ja", "pt", "pt-BR"]);
+    }
+}
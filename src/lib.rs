@@ -9,31 +9,181 @@
 
 pub use self::shared::ModifierSet;
 
+use std::hash::{Hash, Hasher};
+
 mod shared;
 
+mod macros;
+
+/// Items used by the expansions of the exported macros. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::macros::{Deprecation, resolve};
+}
+
+#[cfg(feature = "html")]
+pub mod html;
+
+pub mod ascii;
+
+#[cfg(feature = "l10n")]
+pub mod l10n;
+
 #[cfg(feature = "numeral-systems")]
 pub mod numeral_systems;
 
+pub mod search;
+
+#[cfg(feature = "shorthands")]
+pub mod shorthands;
+
 #[cfg(feature = "styling")]
 pub mod styling;
 
+pub mod template;
+
+pub mod transpile;
+
 /// A module of definitions.
+///
+/// Modules are compared and hashed by identity, which makes them cheap to use
+/// as keys, e.g. for memoization.
 #[derive(Debug, Copy, Clone)]
 pub struct Module(&'static [(&'static str, Binding)]);
 
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Module {}
+
+impl Hash for Module {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state);
+    }
+}
+
 impl Module {
     /// Try to get a bound definition in the module.
-    pub fn get(&self, name: &str) -> Option<Binding> {
-        self.0
-            .binary_search_by_key(&name, |(k, _)| k)
-            .ok()
-            .map(|i| self.0[i].1)
+    ///
+    /// This can also be used in constants.
+    pub const fn get(&self, name: &str) -> Option<Binding> {
+        macros::get(*self, name.as_bytes())
     }
 
     /// Iterate over the module's definition.
     pub fn iter(&self) -> impl Iterator<Item = (&'static str, Binding)> {
         self.0.iter().copied()
     }
+
+    /// Walk over the variants of all symbols in the module and its nested
+    /// modules, depth-first.
+    ///
+    /// See [`Walk`] for details.
+    pub fn walk(&self) -> Walk {
+        Walk {
+            stack: vec![(*self, 0, None)],
+            symbol: None,
+            path: String::new(),
+            skip_deprecated: false,
+            prune: |_, _| false,
+        }
+    }
+}
+
+/// A depth-first iterator over the variants of all symbols in a module and its
+/// nested modules, created by [`Module::walk`].
+///
+/// Each variant is represented by a tuple `(path, modifiers, value,
+/// deprecation)`. The path of the symbol is relative to the walked module, so
+/// walking [`ROOT`] yields fully qualified paths like `sym.arrow`. The
+/// deprecation is that of the variant, or else that of the symbol or of the
+/// innermost module around it that is deprecated.
+pub struct Walk<P = fn(&str, Binding) -> bool> {
+    /// The definitions that are left in each of the modules being walked,
+    /// alongside the length of the module's path and its deprecation.
+    stack: Vec<(Module, usize, Option<&'static str>)>,
+    /// The symbol being walked, alongside the index of its next variant and
+    /// its deprecation.
+    symbol: Option<(Symbol, usize, Option<&'static str>)>,
+    /// The path of the current definition.
+    path: String,
+    /// Whether deprecated variants are skipped.
+    skip_deprecated: bool,
+    /// Whether a definition is skipped.
+    prune: P,
+}
+
+impl<P: FnMut(&str, Binding) -> bool> Walk<P> {
+    /// Skip deprecated variants, including all variants of deprecated symbols
+    /// and modules.
+    pub fn skip_deprecated(mut self) -> Self {
+        self.skip_deprecated = true;
+        self
+    }
+
+    /// Skip the definitions for which `prune` returns `true`, given their path
+    /// and binding. For a module, this skips everything within it.
+    pub fn prune<Q: FnMut(&str, Binding) -> bool>(self, prune: Q) -> Walk<Q> {
+        Walk {
+            stack: self.stack,
+            symbol: self.symbol,
+            path: self.path,
+            skip_deprecated: self.skip_deprecated,
+            prune,
+        }
+    }
+}
+
+impl<P: FnMut(&str, Binding) -> bool> Iterator for Walk<P> {
+    type Item = (String, ModifierSet<&'static str>, &'static str, Option<&'static str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Self { stack, symbol, path, skip_deprecated, prune } = self;
+        loop {
+            if let Some((current, i, deprecation)) = symbol {
+                let variant = match current {
+                    Symbol::Single(c) => {
+                        (*i == 0).then_some((ModifierSet::default(), *c, None))
+                    }
+                    Symbol::Multi(list) => list.get(*i).copied(),
+                };
+                *i += 1;
+                let Some((modifiers, c, variant_deprecation)) = variant else {
+                    *symbol = None;
+                    continue;
+                };
+                let deprecation = variant_deprecation.or(*deprecation);
+                if !(*skip_deprecated && deprecation.is_some()) {
+                    return Some((path.clone(), modifiers, c, deprecation));
+                }
+                continue;
+            }
+
+            let (module, len, deprecation) = stack.last_mut()?;
+            let Some((&(name, binding), rest)) = module.0.split_first() else {
+                stack.pop();
+                continue;
+            };
+            module.0 = rest;
+            path.truncate(*len);
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(name);
+
+            let deprecation = binding.deprecation.or(*deprecation);
+            if (*skip_deprecated && deprecation.is_some()) || prune(path, binding) {
+                continue;
+            }
+            match binding.def {
+                Def::Symbol(s) => *symbol = Some((s, 0, deprecation)),
+                Def::Module(module) => stack.push((module, path.len(), deprecation)),
+            }
+        }
+    }
 }
 
 /// A definition bound in a module, with metadata.
@@ -62,6 +212,11 @@ pub enum Def {
 }
 
 /// A symbol, either a leaf or with modifiers and optional deprecation.
+///
+/// Symbols are compared and hashed by the identity of their data, which makes
+/// them cheap to use as keys, e.g. for memoization. Each definition has its own
+/// data, so symbols with the same value, like `sym.gradient` and `sym.nabla`,
+/// are not equal.
 #[derive(Debug, Copy, Clone)]
 pub enum Symbol {
     /// A symbol without modifiers.
@@ -70,6 +225,28 @@ pub enum Symbol {
     Multi(&'static [(ModifierSet<&'static str>, &'static str, Option<&'static str>)]),
 }
 
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Single(a), Self::Single(b)) => std::ptr::eq(*a, *b),
+            (Self::Multi(a), Self::Multi(b)) => std::ptr::eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Single(value) => std::ptr::hash(*value, state),
+            Self::Multi(list) => std::ptr::hash(*list, state),
+        }
+    }
+}
+
 impl Symbol {
     /// Get the symbol's variant for a given set of modifiers, alongside an optional deprecation
     /// message.
@@ -82,6 +259,25 @@ impl Symbol {
         }
     }
 
+    /// Get the symbol's variant for a given set of modifiers, selected according
+    /// to the given matching mode.
+    ///
+    /// With [`MatchMode::Best`], this selects the same variant as
+    /// [`get`](Self::get).
+    pub fn get_matching<'a>(
+        &self,
+        modifs: ModifierSet<&'a str>,
+        mode: MatchMode,
+    ) -> Option<VariantMatch<'a>> {
+        let variants = self.variants().map(|(m, c, d)| (m, (m, c, d)));
+        let (modifiers, value, deprecation) = match mode {
+            MatchMode::Best => modifs.best_match_in(variants),
+            MatchMode::Exact => modifs.exact_match_in(variants),
+            MatchMode::Closest => modifs.closest_match_in(variants),
+        }?;
+        Some(VariantMatch { modifiers, value, deprecation, requested: modifs })
+    }
+
     /// Iterate over the variants of this symbol.
     ///
     /// Each variant is represented by a tuple `(modifiers, value, deprecation)`.
@@ -115,20 +311,208 @@ impl Symbol {
             .collect::<std::collections::BTreeSet<_>>()
             .into_iter()
     }
+
+    /// Get the canonical target of the variant with exactly the given
+    /// modifiers, if it is an alias.
+    ///
+    /// This works like [`alias_target`], but for a symbol that was already
+    /// looked up, e.g. through [`Module::get`]. Returns `None` if the symbol
+    /// has no such variant or if the variant is not an alias.
+    pub fn alias_target(&self, modifs: ModifierSet<&str>) -> Option<&'static str> {
+        let symbol_at = |path: &str| {
+            let mut def = Def::Module(ROOT);
+            for name in path.split('.') {
+                let Def::Module(module) = def else { return None };
+                def = module.get(name)?.def;
+            }
+            match def {
+                Def::Symbol(symbol) => Some(symbol),
+                Def::Module(_) => None,
+            }
+        };
+        ALIASES
+            .iter()
+            .filter(|&&(_, m, _)| modifs.is_subset(m) && m.is_subset(modifs))
+            .find(|&&(path, _, _)| symbol_at(path) == Some(*self))
+            .map(|&(_, _, target)| target)
+    }
+}
+
+/// How [`Symbol::get_matching`] selects a variant for a set of modifiers.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum MatchMode {
+    /// Select the best variant that has all of the requested modifiers, as
+    /// described in [`ModifierSet::best_match_in`]. Missing modifiers are
+    /// filled in.
+    #[default]
+    Best,
+    /// Select the variant that has exactly the requested modifiers. No
+    /// modifiers are filled in.
+    Exact,
+    /// Select the best variant as with [`Best`](Self::Best) if there is one.
+    /// Otherwise, select the variant that has the most of the requested
+    /// modifiers and drop the others. See [`ModifierSet::closest_match_in`].
+    Closest,
+}
+
+/// A variant selected by [`Symbol::get_matching`].
+#[derive(Debug, Copy, Clone)]
+pub struct VariantMatch<'a> {
+    /// The modifiers of the selected variant.
+    pub modifiers: ModifierSet<&'static str>,
+    /// The value of the selected variant.
+    pub value: &'static str,
+    /// A deprecation message for the selected variant, if it is deprecated.
+    pub deprecation: Option<&'static str>,
+    /// The modifiers that were requested.
+    pub requested: ModifierSet<&'a str>,
+}
+
+impl<'a> VariantMatch<'a> {
+    /// The requested modifiers that the selected variant has.
+    pub fn honored(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.requested.into_iter().filter(|m| self.modifiers.contains(m))
+    }
+
+    /// The requested modifiers that the selected variant doesn't have.
+    pub fn dropped(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.requested.into_iter().filter(|m| !self.modifiers.contains(m))
+    }
+
+    /// The modifiers of the selected variant that weren't requested.
+    pub fn filled_in(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.modifiers.into_iter().filter(|m| !self.requested.contains(m))
+    }
+}
+
+/// Get the canonical target of an alias.
+///
+/// Some symbols and variants are defined as aliases for other variants, with
+/// which they share their value. Given the fully qualified path of a symbol or
+/// variant, like `sym.spacebar`, this returns the fully qualified path of the
+/// variant it is an alias for, like `sym.keyboard.space`. The modifiers in
+/// `path` may be in any order, but must match the variant exactly.
+///
+/// Returns `None` if there is no symbol or variant at the path or if it is not
+/// an alias.
+pub fn alias_target(path: &str) -> Option<&'static str> {
+    lookup_table(ALIASES, path)
+}
+
+/// Get the description of a module, symbol, or variant.
+///
+/// Given a fully qualified path like `sym.control.nul`, this returns a short
+/// description like `NULL`, if there is one. Descriptions may span multiple
+/// lines. The modifiers in `path` may be in any order, but must match the
+/// variant exactly.
+pub fn description(path: &str) -> Option<&'static str> {
+    lookup_table(DESCRIPTIONS, path)
+}
+
+/// Get the speech text of a variant, for screen readers.
+///
+/// Given a fully qualified path like `sym.arrow.r.long`, this returns a text
+/// like `long right arrow` that can be used as alternative text, if there is
+/// one. Most speech texts are derived from the speech text of the symbol and
+/// the modifiers, e.g. `not subset of or equal to` for `sym.subset.eq.not`.
+/// The modifiers in `path` may be in any order, but must match the variant
+/// exactly.
+pub fn speech(path: &str) -> Option<&'static str> {
+    lookup_table(SPEECH, path)
+}
+
+/// Get the stable ID of a variant.
+///
+/// Every variant has a numeric ID that stays the same across releases and is
+/// never reused for another variant, so it can be used instead of the fully
+/// qualified path, like `sym.arrow.r`, when storing or transmitting a
+/// reference to a variant. The modifiers in `path` may be in any order, but
+/// must match the variant exactly.
+pub fn variant_id(path: &str) -> Option<u32> {
+    lookup_table(VARIANT_IDS, path)
+}
+
+/// Get a variant by its stable ID.
+///
+/// Returns a tuple `(path, modifiers, value)`, where `path` is the fully
+/// qualified path of the symbol. See [`variant_id`] for details.
+pub fn variant_by_id(
+    id: u32,
+) -> Option<(&'static str, ModifierSet<&'static str>, &'static str)> {
+    VARIANTS_BY_ID
+        .binary_search_by_key(&id, |&(id, ..)| id)
+        .ok()
+        .map(|i| {
+            let (_, path, modifiers, value) = VARIANTS_BY_ID[i];
+            (path, modifiers, value)
+        })
+}
+
+/// Looks up the entry for a module, symbol, or variant in a table generated by
+/// the build script.
+fn lookup_table<T: Copy>(
+    table: &'static [(&'static str, ModifierSet<&'static str>, T)],
+    path: &str,
+) -> Option<T> {
+    let (prefix, modifs) = split_path(path)?;
+    let start = table.partition_point(|&(p, _, _)| p < prefix);
+    table[start..]
+        .iter()
+        .take_while(|&&(p, _, _)| p == prefix)
+        .find(|&&(_, m, _)| modifs.is_subset(m) && m.is_subset(modifs))
+        .map(|&(_, _, value)| value)
+}
+
+/// Splits a fully qualified path into the path of the module or symbol and the
+/// modifiers.
+///
+/// Returns `None` if there is no module or symbol at the path.
+fn split_path(path: &str) -> Option<(&str, ModifierSet<&str>)> {
+    if path.split('.').any(str::is_empty) {
+        return None;
+    }
+
+    let mut module = ROOT;
+    let mut end = 0;
+    for name in path.split('.') {
+        end += name.len();
+        match module.get(name)?.def {
+            Def::Module(inner) => module = inner,
+            Def::Symbol(_) => {
+                let modifs = path.get(end + 1..).unwrap_or_default();
+                return Some((&path[..end], ModifierSet::from_raw_dotted(modifs)));
+            }
+        }
+        end += 1;
+    }
+
+    Some((path, ModifierSet::default()))
 }
 
 /// A module that contains the other top-level modules.
-pub const ROOT: Module = Module(&[
-    ("emoji", Binding::new(Def::Module(EMOJI))),
-    ("sym", Binding::new(Def::Module(SYM))),
-]);
+///
+/// Only the modules whose cargo features are enabled are included.
+pub const ROOT: Module = Module(&ROOT_DEFS);
+
+/// Turns bytes that live in a static into a string with the same address, so
+/// that the values of different symbols are never merged.
+#[cfg(any(feature = "sym", feature = "emoji"))]
+const fn static_str(bytes: &'static [u8]) -> &'static str {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => string,
+        Err(_) => panic!("value is not UTF-8"),
+    }
+}
 
 include!(concat!(env!("OUT_DIR"), "/out.rs"));
 
 #[cfg(test)]
 mod test {
     use super::*;
+    #[cfg(feature = "sym")]
     use std::collections::BTreeSet;
+    #[cfg(all(feature = "_test-unicode-conformance", feature = "emoji"))]
+    use std::collections::HashMap;
     #[cfg(feature = "_test-unicode-conformance")]
     use std::collections::HashSet;
 
@@ -147,6 +531,7 @@ mod test {
         assert_sorted_recursively(ROOT);
     }
 
+    #[cfg(feature = "sym")]
     #[test]
     fn unicode_escapes() {
         let Def::Symbol(wj) = SYM.get("wj").unwrap().def else { panic!() };
@@ -159,6 +544,231 @@ mod test {
         );
     }
 
+    #[cfg(feature = "sym")]
+    #[test]
+    fn matching_modes() {
+        let Def::Symbol(arrow) = SYM.get("arrow").unwrap().def else { panic!() };
+        let get = |modifs, mode| {
+            let m = arrow.get_matching(ModifierSet::from_raw_dotted(modifs), mode)?;
+            let honored = m.honored().collect::<Vec<_>>();
+            let dropped = m.dropped().collect::<Vec<_>>();
+            let filled_in = m.filled_in().collect::<Vec<_>>();
+            Some((m.value, honored, dropped, filled_in))
+        };
+
+        assert_eq!(
+            get("double", MatchMode::Best),
+            Some(("⇒", vec!["double"], vec![], vec!["r"]))
+        );
+        assert_eq!(get("double", MatchMode::Exact), None);
+        assert_eq!(
+            get("double.r", MatchMode::Exact),
+            Some(("⇒", vec!["double", "r"], vec![], vec![]))
+        );
+        assert_eq!(get("r.double.foo", MatchMode::Best), None);
+        assert_eq!(
+            get("r.double.foo", MatchMode::Closest),
+            Some(("⇒", vec!["r", "double"], vec!["foo"], vec![]))
+        );
+    }
+
+    #[cfg(feature = "sym")]
+    #[test]
+    fn aliases() {
+        assert_eq!(alias_target("sym.spacebar"), Some("sym.keyboard.space"));
+        assert_eq!(alias_target("sym.prec.eq.curly"), Some("sym.prec.eq.slant"));
+        assert_eq!(alias_target("sym.prec.curly.eq.not"), Some("sym.prec.eq.slant.not"));
+        assert_eq!(alias_target("sym.prec.curly"), None);
+        assert_eq!(alias_target("sym.prec.eq.slant"), None);
+        assert_eq!(alias_target("sym.keyboard"), None);
+        assert_eq!(alias_target("sym.keyboard.foo"), None);
+        assert_eq!(alias_target("sym..spacebar"), None);
+
+        let Def::Symbol(spacebar) = SYM.get("spacebar").unwrap().def else { panic!() };
+        assert_eq!(
+            spacebar.alias_target(ModifierSet::default()),
+            Some("sym.keyboard.space")
+        );
+        let Def::Symbol(prec) = SYM.get("prec").unwrap().def else { panic!() };
+        let curly = ModifierSet::from_raw_dotted("eq.curly");
+        assert_eq!(prec.alias_target(curly), Some("sym.prec.eq.slant"));
+        assert_eq!(prec.alias_target(ModifierSet::from_raw_dotted("curly")), None);
+        assert_eq!(prec.alias_target(ModifierSet::default()), None);
+        let Def::Symbol(succ) = SYM.get("succ").unwrap().def else { panic!() };
+        assert_eq!(succ.alias_target(curly), Some("sym.succ.eq.slant"));
+
+        let Def::Symbol(spacebar) = SYM.get("spacebar").unwrap().def else { panic!() };
+        assert_eq!(spacebar.get(ModifierSet::default()).unwrap().0, "␣");
+    }
+
+    #[cfg(feature = "sym-control")]
+    #[test]
+    fn descriptions() {
+        assert_eq!(description("sym.control.nul"), Some("NULL"));
+        assert_eq!(description("sym.control.dc"), Some("Device Control"));
+        assert_eq!(description("sym.control.dc.one"), None);
+        assert_eq!(
+            description("sym.control"),
+            Some("Printable characters representing control (non-printable) characters")
+        );
+        assert_eq!(description("sym.arrow"), None);
+        assert_eq!(description("sym.control.foo"), None);
+    }
+
+    #[cfg(all(feature = "sym-control", feature = "emoji"))]
+    #[test]
+    fn walk() {
+        fn find<P: FnMut(&str, Binding) -> bool>(
+            mut walk: Walk<P>,
+            path: &str,
+            modifs: &str,
+        ) -> Option<(&'static str, Option<&'static str>)> {
+            walk.find(|(p, m, _, _)| p == path && m.as_str() == modifs)
+                .map(|(_, _, c, d)| (c, d))
+        }
+
+        assert_eq!(find(ROOT.walk(), "sym.arrow", "r.double"), Some(("⇒", None)));
+        assert_eq!(find(SYM.walk(), "arrow", "r.double"), Some(("⇒", None)));
+        assert_eq!(find(SYM.walk(), "control.nul", ""), Some(("␀", None)));
+        assert!(find(SYM.walk(), "spacebar", "").unwrap().1.is_some());
+        assert_eq!(find(SYM.walk().skip_deprecated(), "spacebar", ""), None);
+
+        let pruned = SYM.walk().prune(|path, _| path == "control");
+        assert_eq!(find(pruned, "control.nul", ""), None);
+        let pruned = SYM.walk().prune(|path, _| path == "control");
+        assert!(pruned.filter(|(p, ..)| p == "arrow").count() > 1);
+
+        let count = |module: Module| {
+            module
+                .iter()
+                .map(|(_, binding)| match binding.def {
+                    Def::Symbol(s) => s.variants().count(),
+                    Def::Module(_) => 0,
+                })
+                .sum::<usize>()
+        };
+        assert_eq!(
+            ROOT.walk().prune(|path, _| path.matches('.').count() > 1).count(),
+            count(SYM) + count(EMOJI),
+        );
+    }
+
+    #[cfg(feature = "sym")]
+    #[test]
+    fn speech() {
+        assert_eq!(super::speech("sym.arrow.r.long"), Some("long right arrow"));
+        assert_eq!(super::speech("sym.arrow.long.r"), Some("long right arrow"));
+        assert_eq!(super::speech("sym.arrow.r.double"), Some("right double arrow"));
+        assert_eq!(super::speech("sym.subset.eq.not"), Some("not subset of or equal to"));
+        assert_eq!(super::speech("sym.lt"), Some("less than"));
+        assert_eq!(super::speech("sym.plus.minus"), Some("plus or minus"));
+        assert_eq!(super::speech("sym.arrow"), None);
+
+        // Negated, reversed, and circled operators whose text can't be
+        // derived by putting words around the symbol's text.
+        assert_eq!(super::speech("sym.eq.not"), Some("not equal to"));
+        assert_eq!(super::speech("sym.eq.triple.not"), Some("not identical to"));
+        assert_eq!(super::speech("sym.prec.not"), Some("does not precede"));
+        assert_eq!(super::speech("sym.succ.not"), Some("does not succeed"));
+        assert_eq!(super::speech("sym.exists.not"), Some("there does not exist"));
+        assert_eq!(super::speech("sym.in.rev"), Some("contains as member"));
+        assert_eq!(super::speech("sym.in.rev.not"), Some("does not contain as member"));
+        assert_eq!(super::speech("sym.in.rev.small"), Some("small contains as member"));
+        assert_eq!(super::speech("sym.div.o"), Some("circled division sign"));
+
+        // Those whose text can be derived.
+        assert_eq!(super::speech("sym.in.not"), Some("not element of"));
+        assert_eq!(super::speech("sym.gt.eq.not"), Some("not greater than or equal to"));
+        assert_eq!(super::speech("sym.times.o"), Some("circled times"));
+        assert_eq!(super::speech("sym.plus.o.big"), Some("big circled plus"));
+        assert_eq!(super::speech("sym.emptyset.rev"), Some("reversed empty set"));
+    }
+
+    #[cfg(feature = "sym")]
+    #[test]
+    fn variant_ids() {
+        for (path, modifiers, value, _) in ROOT.walk() {
+            let qualified = format!("{path}.{}", modifiers.as_str());
+            let id = variant_id(qualified.trim_end_matches('.')).unwrap();
+            assert_eq!(variant_by_id(id), Some((path.as_str(), modifiers, value)));
+        }
+
+        let id = variant_id("sym.arrow.r.double").unwrap();
+        assert_eq!(variant_id("sym.arrow.double.r"), Some(id));
+        assert_ne!(variant_id("sym.arrow.r"), Some(id));
+        assert_eq!(variant_id("sym.arrow.double"), None);
+        assert_eq!(variant_id("sym.arrow.r.double.foo"), None);
+        assert_eq!(variant_by_id(0), None);
+    }
+
+    #[cfg(feature = "sym-control")]
+    #[test]
+    fn identity() {
+        let get = |module: Module, name| module.get(name).unwrap().def;
+        let (Def::Symbol(a), Def::Symbol(b)) = (get(SYM, "arrow"), get(SYM, "arrow"))
+        else {
+            panic!()
+        };
+        let Def::Symbol(c) = get(SYM, "harpoon") else { panic!() };
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        // Symbols with the same value are still told apart.
+        let (Def::Symbol(gradient), Def::Symbol(nabla)) =
+            (get(SYM, "gradient"), get(SYM, "nabla"))
+        else {
+            panic!()
+        };
+        assert_eq!(
+            gradient.get(ModifierSet::default()),
+            nabla.get(ModifierSet::default())
+        );
+        assert_ne!(gradient, nabla);
+
+        let Def::Module(control) = get(SYM, "control") else { panic!() };
+        assert_eq!(control, control);
+        assert_ne!(control, SYM);
+        let Def::Module(sym) = get(ROOT, "sym") else { panic!() };
+        assert_eq!(sym, SYM);
+
+        let set = [a, b, c].into_iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[cfg(all(feature = "sym-control", feature = "emoji"))]
+    #[test]
+    #[allow(deprecated)]
+    fn macros() {
+        const ARROW: &str = sym!(arrow.r.long);
+        assert_eq!(ARROW, "⟶");
+        assert_eq!(sym!(arrow.long.r), "⟶");
+        assert_eq!(sym!(arrow), "→");
+        assert_eq!(sym!(control.nul), "␀");
+        assert_eq!(sym!(spacebar), "␣");
+        assert_eq!(emoji!(face.grin), "😀");
+        const SPACEBAR: Option<Binding> = SYM.get("spacebar");
+        assert!(SPACEBAR.unwrap().deprecation.is_some());
+
+        // The warning carries the real deprecation message.
+        assert_eq!(__private::resolve(SYM, "arrow.r").1, 0);
+        let (value, index) = __private::resolve(SYM, "spacebar");
+        assert_eq!(value, "␣");
+        assert_eq!(Some(DEPRECATIONS[index - 1]), SPACEBAR.unwrap().deprecation);
+
+        let mut n = 0;
+        for (path, modifiers, value, _) in SYM.walk() {
+            let path = format!("{path}.{}", modifiers.as_str());
+            if let Ok((resolved, _)) =
+                macros::lookup(SYM, path.trim_end_matches('.').as_bytes())
+            {
+                assert_eq!(resolved, value, "{path}");
+                n += 1;
+            }
+        }
+        assert!(n > 1000);
+    }
+
+    #[cfg(feature = "sym")]
     #[test]
     fn random_sample() {
         for (key, control) in [
@@ -192,10 +802,13 @@ mod test {
     }
 
     /// https://www.unicode.org/reports/tr51/#def_text_presentation_selector.
+    #[cfg(any(feature = "emoji", feature = "_test-unicode-conformance"))]
     const TEXT_PRESENTATION_SELECTOR: char = '\u{FE0E}';
     /// https://www.unicode.org/reports/tr51/#def_emoji_presentation_selector.
+    #[cfg(any(feature = "sym", feature = "_test-unicode-conformance"))]
     const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
 
+    #[cfg(feature = "sym")]
     #[test]
     fn symbols_are_not_emojis() {
         assert!(
@@ -204,6 +817,7 @@ mod test {
         )
     }
 
+    #[cfg(feature = "emoji")]
     #[test]
     fn emojis_are_not_text() {
         assert!(
@@ -254,7 +868,7 @@ mod test {
         )
     }
 
-    #[cfg(feature = "_test-unicode-conformance")]
+    #[cfg(all(feature = "_test-unicode-conformance", feature = "sym"))]
     #[test]
     fn symbols_have_text_presentation() {
         let require_presentation_selector = get_valid_presentation_sequences()
@@ -272,7 +886,7 @@ mod test {
         )
     }
 
-    #[cfg(feature = "_test-unicode-conformance")]
+    #[cfg(all(feature = "_test-unicode-conformance", feature = "emoji"))]
     #[test]
     fn emojis_have_emoji_presentation() {
         let require_presentation_selector = get_valid_presentation_sequences()
@@ -290,50 +904,117 @@ mod test {
         )
     }
 
+    /// Returns the general category of each assigned codepoint, as ranges of
+    /// codepoints.
+    #[cfg(feature = "_test-unicode-conformance")]
+    fn get_general_categories() -> Vec<(u32, u32, &'static str)> {
+        let mut categories = vec![];
+        let mut first = None;
+        for line in include_str!(concat!(env!("OUT_DIR"), "/UnicodeData.txt")).lines() {
+            let fields = line.split(';').collect::<Vec<_>>();
+            let cp = u32::from_str_radix(fields[0], 0x10).unwrap();
+            if fields[1].ends_with(", First>") {
+                first = Some(cp);
+                continue;
+            }
+            categories.push((first.take().unwrap_or(cp), cp, fields[2]));
+        }
+        categories
+    }
+
+    #[cfg(feature = "_test-unicode-conformance")]
+    #[test]
+    fn all_codepoints_assigned() {
+        let categories = get_general_categories();
+        let category = |c: char| {
+            let i = categories.partition_point(|&(_, last, _)| last < c as u32);
+            categories
+                .get(i)
+                .filter(|&&(first, _, _)| first <= c as u32)
+                .map(|&(_, _, category)| category)
+        };
+        assert!(
+            are_all_variants_valid(ROOT, |c| c.chars().all(|c| category(c).is_some())),
+            "unassigned codepoint(s) (see list above)",
+        );
+        assert!(
+            are_all_variants_valid(ROOT, |c| c
+                .chars()
+                .all(|c| category(c) != Some("Co"))),
+            "private-use codepoint(s) (see list above)",
+        );
+    }
+
+    #[cfg(feature = "_test-unicode-conformance")]
+    #[test]
+    fn no_noncharacters() {
+        assert!(
+            are_all_variants_valid(ROOT, |c| {
+                c.chars().all(|c| {
+                    !(0xFDD0..=0xFDEF).contains(&(c as u32))
+                        && (c as u32) & 0xFFFE != 0xFFFE
+                })
+            }),
+            "noncharacter(s) (see list above)",
+        )
+    }
+
+    #[cfg(all(feature = "_test-unicode-conformance", feature = "emoji"))]
+    #[test]
+    fn emojis_fully_qualified() {
+        let statuses = include_str!(concat!(env!("OUT_DIR"), "/emoji-test.txt"))
+            .lines()
+            .filter_map(|l| l.split('#').next().unwrap().split_once(';'))
+            .map(|(codepoints, status)| {
+                let sequence = codepoints
+                    .split_whitespace()
+                    .map(|cp| {
+                        char::from_u32(u32::from_str_radix(cp, 0x10).unwrap()).unwrap()
+                    })
+                    .collect::<String>();
+                (sequence, status.trim())
+            })
+            .collect::<HashMap<_, _>>();
+        assert!(
+            are_all_variants_valid(EMOJI, |c| {
+                // Skin tones and hair styles are components, which are only
+                // meant to be used in sequences with other emoji.
+                matches!(statuses.get(c), Some(&("fully-qualified" | "component")))
+            }),
+            "emoji that aren't fully qualified (see list above)",
+        )
+    }
+
     /// Returns `false` if, and only if, the predicate returned `false` for at least one variant
     /// within the module.
     ///
     /// Prints all variants for which the predicate returns `false`.
+    #[cfg(any(
+        feature = "sym",
+        feature = "emoji",
+        feature = "_test-unicode-conformance"
+    ))]
     fn are_all_variants_valid<P: FnMut(&'static str) -> bool>(
         module: Module,
         mut predicate: P,
     ) -> bool {
         let mut all_valid = true;
-        fn aux<P: FnMut(&'static str) -> bool>(
-            module: Module,
-            path: Vec<&'static str>,
-            all_valid: &mut bool,
-            predicate: &mut P,
-        ) {
-            for (name, binding) in module.iter() {
-                let mut new_path = path.clone();
-                new_path.push(name);
-                match binding.def {
-                    Def::Symbol(s) => {
-                        for (modifiers, c, _) in s.variants() {
-                            if !predicate(c) {
-                                *all_valid = false;
-                                eprintln!(
-                                    "- {}{}{} {} ({})",
-                                    new_path.join("."),
-                                    if modifiers.is_empty() { "" } else { "." },
-                                    modifiers.as_str(),
-                                    c,
-                                    c.chars()
-                                        .map(|cp| format!("{:04X}", cp as u32))
-                                        .collect::<Vec<_>>()
-                                        .join(" "),
-                                )
-                            }
-                        }
-                    }
-                    Def::Module(m) => {
-                        aux(m, new_path, all_valid, predicate);
-                    }
-                }
+        for (path, modifiers, c, _) in module.walk() {
+            if !predicate(c) {
+                all_valid = false;
+                eprintln!(
+                    "- {}{}{} {} ({})",
+                    path,
+                    if modifiers.is_empty() { "" } else { "." },
+                    modifiers.as_str(),
+                    c,
+                    c.chars()
+                        .map(|cp| format!("{:04X}", cp as u32))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
             }
         }
-        aux(module, Vec::new(), &mut all_valid, &mut predicate);
         all_valid
     }
 }
@@ -0,0 +1,278 @@
+//! Compile-time lookups for the [`sym!`](crate::sym) and
+//! [`emoji!`](crate::emoji) macros.
+
+use std::cmp::Ordering;
+
+use crate::{Binding, Def, Module, Symbol};
+
+/// Get the value of a symbol or variant in `sym` at compile time.
+///
+/// The path is written like in Typst, without the leading `sym`. It resolves
+/// to the same variant as a lookup at runtime and expands to a
+/// `&'static str`, which can also be used in constants.
+///
+/// ```
+/// assert_eq!(codex::sym!(arrow.r.long), "⟶");
+/// assert_eq!(codex::sym!(arrow.long.r), "⟶");
+///
+/// const DOUBLE: &str = codex::sym!(arrow.r.double);
+/// assert_eq!(DOUBLE, "⇒");
+/// ```
+///
+/// Unknown names, modifiers that no variant has, and modifiers that match
+/// multiple variants with different values equally well are compile errors.
+/// Deprecated symbols and variants produce a warning with their deprecation
+/// message.
+///
+/// ```compile_fail
+/// let _ = codex::sym!(arrow.foo);
+/// ```
+///
+/// ```compile_fail
+/// // Both `arrow.r.double` and `arrow.l.double` match.
+/// let _ = codex::sym!(arrow.double);
+/// ```
+#[cfg(feature = "sym")]
+#[macro_export]
+macro_rules! sym {
+    ($($path:tt)+) => {
+        $crate::__resolve!($crate::SYM, $($path)+)
+    };
+}
+
+/// Get the value of an emoji or one of its variants at compile time.
+///
+/// This works just like [`sym!`], but for the `emoji` module.
+///
+/// ```
+/// assert_eq!(codex::emoji!(face.grin), "😀");
+/// ```
+#[cfg(feature = "emoji")]
+#[macro_export]
+macro_rules! emoji {
+    ($($path:tt)+) => {
+        $crate::__resolve!($crate::EMOJI, $($path)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __resolve {
+    ($module:expr, $first:ident $(. $rest:ident)*) => {{
+        const RESOLVED: (&str, usize) = $crate::__private::resolve(
+            $module,
+            concat!(stringify!($first) $(, ".", stringify!($rest))*),
+        );
+        $crate::__private::Deprecation::<{ RESOLVED.1 }>::check();
+        RESOLVED.0
+    }};
+}
+
+/// Emits a deprecation warning when [`check`](Self::check) is called.
+///
+/// `INDEX` is zero for definitions that aren't deprecated and one more than
+/// the index of the message in `DEPRECATIONS` otherwise. The build script
+/// generates a `check` function that is deprecated with the right message for
+/// each of the latter.
+pub struct Deprecation<const INDEX: usize>;
+
+impl Deprecation<0> {
+    pub const fn check() {}
+}
+
+/// Resolves a path relative to a module to the value of a variant and the
+/// index of its [`Deprecation`], panicking if it can't be resolved
+/// unambiguously.
+pub const fn resolve(module: Module, path: &str) -> (&'static str, usize) {
+    match lookup(module, path.as_bytes()) {
+        Ok((value, None)) => (value, 0),
+        Ok((value, Some(message))) => (value, deprecation_index(message.as_bytes())),
+        Err(message) => panic!("{}", message),
+    }
+}
+
+/// Finds the index of the [`Deprecation`] for a deprecation message.
+const fn deprecation_index(message: &[u8]) -> usize {
+    let mut low = 0;
+    let mut high = crate::DEPRECATIONS.len();
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match compare(crate::DEPRECATIONS[mid].as_bytes(), message) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return mid + 1,
+        }
+    }
+    panic!("unknown deprecation message")
+}
+
+/// Resolves a path relative to a module to the value of a variant and its
+/// deprecation, or the deprecation of the symbol or a module around it.
+pub(crate) const fn lookup(
+    mut module: Module,
+    mut path: &[u8],
+) -> Result<(&'static str, Option<&'static str>), &'static str> {
+    let mut deprecation = None;
+    loop {
+        let (name, tail) = split_dot(path);
+        let Some(binding) = get(module, name) else {
+            return Err("unknown symbol or module");
+        };
+        if binding.deprecation.is_some() {
+            deprecation = binding.deprecation;
+        }
+        match (binding.def, tail) {
+            (Def::Module(inner), Some(tail)) => {
+                module = inner;
+                path = tail;
+            }
+            (Def::Module(_), None) => return Err("expected a symbol, found a module"),
+            (Def::Symbol(symbol), modifs) => {
+                let (value, variant_deprecation) = match select(symbol, modifs) {
+                    Ok(selected) => selected,
+                    Err(message) => return Err(message),
+                };
+                if variant_deprecation.is_some() {
+                    deprecation = variant_deprecation;
+                }
+                return Ok((value, deprecation));
+            }
+        }
+    }
+}
+
+/// Selects the variant of a symbol for some modifiers, like
+/// [`Symbol::get`](crate::Symbol::get), but fails if multiple variants with
+/// different values match equally well.
+const fn select(
+    symbol: Symbol,
+    modifs: Option<&[u8]>,
+) -> Result<(&'static str, Option<&'static str>), &'static str> {
+    let list = match (symbol, modifs) {
+        (Symbol::Single(value), None) => return Ok((value, None)),
+        (Symbol::Single(_), Some(_)) => return Err("symbol has no modifiers"),
+        (Symbol::Multi(list), _) => list,
+    };
+    let modifs = match modifs {
+        Some(modifs) => modifs,
+        None => &[],
+    };
+
+    // The index of the best match, the number of its modifiers in common with
+    // `modifs`, and its total number of modifiers.
+    let mut best: Option<(usize, usize, usize)> = None;
+    let mut ambiguous = false;
+    let mut i = 0;
+    while i < list.len() {
+        let (set, value, _) = list[i];
+        let (inside, requested) = count(modifs, set.0.as_bytes());
+        if inside == requested {
+            let (matching, total) = count(set.0.as_bytes(), modifs);
+            match best {
+                Some((b, m, t)) if matching == m && total == t => {
+                    if !matches!(
+                        compare(list[b].1.as_bytes(), value.as_bytes()),
+                        Ordering::Equal
+                    ) {
+                        ambiguous = true;
+                    }
+                }
+                Some((_, m, t)) if matching < m || (matching == m && total > t) => {}
+                _ => {
+                    best = Some((i, matching, total));
+                    ambiguous = false;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    match best {
+        None => Err("no variant has all of these modifiers"),
+        // Without modifiers, a symbol falls back to its first variant.
+        Some(_) if ambiguous && !modifs.is_empty() => {
+            Err("ambiguous modifiers: multiple variants match equally well")
+        }
+        Some((i, ..)) => Ok((list[i].1, list[i].2)),
+    }
+}
+
+/// Gets a definition in a module by its name.
+pub(crate) const fn get(module: Module, name: &[u8]) -> Option<Binding> {
+    let mut low = 0;
+    let mut high = module.0.len();
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match compare(module.0[mid].0.as_bytes(), name) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Some(module.0[mid].1),
+        }
+    }
+    None
+}
+
+/// Counts the modifiers in the `.`-separated `set` that are also in `other`,
+/// alongside the total number of modifiers in `set`.
+const fn count(mut set: &[u8], other: &[u8]) -> (usize, usize) {
+    let mut common = 0;
+    let mut total = 0;
+    while !set.is_empty() {
+        let (modifier, tail) = split_dot(set);
+        if contains(other, modifier) {
+            common += 1;
+        }
+        total += 1;
+        set = match tail {
+            Some(tail) => tail,
+            None => &[],
+        };
+    }
+    (common, total)
+}
+
+/// Whether the `.`-separated `set` contains `modifier`.
+const fn contains(mut set: &[u8], modifier: &[u8]) -> bool {
+    while !set.is_empty() {
+        let (head, tail) = split_dot(set);
+        if matches!(compare(head, modifier), Ordering::Equal) {
+            return true;
+        }
+        set = match tail {
+            Some(tail) => tail,
+            None => &[],
+        };
+    }
+    false
+}
+
+/// Splits off the part before the first `.`, alongside the rest after it.
+const fn split_dot(s: &[u8]) -> (&[u8], Option<&[u8]>) {
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'.' {
+            let (head, tail) = s.split_at(i);
+            return (head, Some(tail.split_at(1).1));
+        }
+        i += 1;
+    }
+    (s, None)
+}
+
+/// Compares two strings by their bytes, like [`Ord`] for [`str`].
+const fn compare(a: &[u8], b: &[u8]) -> Ordering {
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return if a[i] < b[i] { Ordering::Less } else { Ordering::Greater };
+        }
+        i += 1;
+    }
+    if a.len() < b.len() {
+        Ordering::Less
+    } else if a.len() > b.len() {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
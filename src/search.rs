@@ -0,0 +1,171 @@
+//! Search for symbols by their names, tags, and sections.
+//!
+//! ```
+//! # #[cfg(feature = "sym")] {
+//! let hits = codex::search::search("therefore");
+//! assert_eq!(hits[0].value, "∴");
+//!
+//! let hits = codex::search::search("tick");
+//! assert!(hits.iter().any(|hit| hit.value == "✓"));
+//! # }
+//! ```
+
+use crate::{ModifierSet, SEARCH};
+
+/// A variant in the search index.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Entry {
+    /// The fully qualified path of the symbol.
+    pub(crate) path: &'static str,
+    /// The modifiers of the variant.
+    pub(crate) modifiers: ModifierSet<&'static str>,
+    /// The value of the variant.
+    pub(crate) value: &'static str,
+    /// The lowercase tags of the variant and of the symbol and modules around
+    /// it.
+    pub(crate) tags: &'static [&'static str],
+    /// The section of the file the variant is in, if any.
+    pub(crate) section: Option<&'static str>,
+}
+
+/// A search result.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Hit {
+    /// The fully qualified path of the symbol.
+    pub path: &'static str,
+    /// The modifiers of the variant.
+    pub modifiers: ModifierSet<&'static str>,
+    /// The value of the variant.
+    pub value: &'static str,
+    /// How well the variant matches the query. Higher is better.
+    pub score: u32,
+}
+
+/// Search for variants that match a query.
+///
+/// The query is split into words, which are matched case-insensitively.
+/// Every word must match the variant's name, one of its tags, or its
+/// section, i.e. the category it is listed under, like "Set theory". The
+/// results are ranked by how well the words match: Exact matches of the name
+/// of the symbol or variant count the most, then names that start with the
+/// word, then tags, and finally sections. Among variants that match equally
+/// well, shorter names come first. Deprecated symbols and variants are never
+/// found.
+pub fn search(query: &str) -> Vec<Hit> {
+    let words = query.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+    if words.is_empty() {
+        return vec![];
+    }
+
+    let mut hits = SEARCH
+        .iter()
+        .filter_map(|entry| {
+            let score = words
+                .iter()
+                .map(|word| score(entry, word))
+                .try_fold(0, |total, score| (score > 0).then_some(total + score))?;
+            Some(Hit {
+                path: entry.path,
+                modifiers: entry.modifiers,
+                value: entry.value,
+                score,
+            })
+        })
+        .collect::<Vec<_>>();
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| name_len(a).cmp(&name_len(b)))
+            .then_with(|| {
+                (a.path, a.modifiers.as_str()).cmp(&(b.path, b.modifiers.as_str()))
+            })
+    });
+    hits
+}
+
+/// How well a lowercase word matches an entry, or zero if it doesn't.
+fn score(entry: &Entry, word: &str) -> u32 {
+    let qualified = match entry.modifiers.as_str() {
+        "" => entry.path.to_lowercase(),
+        modifiers => format!("{}.{modifiers}", entry.path.to_lowercase()),
+    };
+    let relative = qualified.split_once('.').map_or(qualified.as_str(), |(_, rest)| rest);
+    let name = entry.path.rsplit('.').next().unwrap().to_lowercase();
+
+    let name = if word == qualified || word == relative {
+        110
+    } else if word == name {
+        100
+    } else if name.starts_with(word) || relative.starts_with(word) {
+        50
+    } else if entry.modifiers.into_iter().any(|modifier| modifier == word) {
+        40
+    } else {
+        0
+    };
+    let tags = if entry.tags.contains(&word) {
+        30
+    } else if entry.tags.iter().any(|tag| tag.starts_with(word)) {
+        20
+    } else {
+        0
+    };
+    let section = entry.section.is_some_and(|section| {
+        section
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|part| part.eq_ignore_ascii_case(word))
+    });
+
+    name.max(tags).max(if section { 10 } else { 0 })
+}
+
+/// The length of the name of a hit, for ranking shorter names first.
+fn name_len(hit: &Hit) -> usize {
+    hit.path.len() + hit.modifiers.as_str().len()
+}
+
+#[cfg(all(test, feature = "sym", feature = "emoji"))]
+mod tests {
+    use super::*;
+
+    /// The value and the name of the best hit for a query.
+    fn best(query: &str) -> (&'static str, String) {
+        let hit = search(query)[0];
+        let name = match hit.modifiers.as_str() {
+            "" => hit.path.to_owned(),
+            modifiers => format!("{}.{modifiers}", hit.path),
+        };
+        (hit.value, name)
+    }
+
+    #[test]
+    fn ranking() {
+        assert_eq!(best("therefore"), ("∴", "sym.therefore".into()));
+        assert_eq!(best("empty"), ("∅", "sym.emptyset".into()));
+        assert_eq!(best("integral"), ("∫", "sym.integral".into()));
+        assert_eq!(best("heart").1, "emoji.heart");
+        assert_eq!(best("arrow.r"), ("→", "sym.arrow.r".into()));
+        assert_eq!(best("arrow double r"), ("⇒", "sym.arrow.r.double".into()));
+        assert_eq!(best("Hence").1, "sym.therefore");
+    }
+
+    #[test]
+    fn scores() {
+        let score = |query: &str, name: &str| {
+            search(query)
+                .into_iter()
+                .find(|hit| {
+                    name.strip_prefix(hit.path).is_some_and(|rest| {
+                        rest.trim_start_matches('.') == hit.modifiers.as_str()
+                    })
+                })
+                .map(|hit| hit.score)
+        };
+        assert!(score("checkmark", "sym.checkmark") > score("tick", "sym.checkmark"));
+        assert!(score("check", "sym.checkmark") > score("tick", "sym.checkmark"));
+        assert!(score("tick", "sym.checkmark") > score("logic", "sym.therefore"));
+        assert_eq!(score("spacebar", "sym.spacebar"), None);
+        assert!(search("").is_empty());
+        assert!(search("xyzzy").is_empty());
+    }
+}
@@ -81,24 +81,86 @@ impl<S: Deref<Target = str>> ModifierSet<S> {
     pub fn best_match_in<'a, T>(
         &self,
         variants: impl Iterator<Item = (ModifierSet<&'a str>, T)>,
+    ) -> Option<T> {
+        Self::max_by_score(variants, |set| self.match_score(set))
+    }
+
+    /// Finds the exact match from the list.
+    ///
+    /// To be considered a match, the modifier set must contain exactly the
+    /// modifiers in `self`, in any order. If there are multiple exact matches,
+    /// the first of them is returned.
+    pub fn exact_match_in<'a, T>(
+        &self,
+        variants: impl Iterator<Item = (ModifierSet<&'a str>, T)>,
+    ) -> Option<T> {
+        variants
+            .filter(|(set, _)| self.is_subset(*set) && set.is_subset(self.as_deref()))
+            .map(|(_, value)| value)
+            .next()
+    }
+
+    /// Finds the closest match from the list, even if no modifier set is a
+    /// superset of `self`.
+    ///
+    /// Every modifier set is considered a match and the best one is selected
+    /// by the same criteria as in [`best_match_in`](Self::best_match_in).
+    /// Since a superset of `self` has more modifiers in common with `self` than
+    /// any other set, this returns the same as `best_match_in` if that finds a
+    /// match.
+    ///
+    /// If there are multiple best matches, the first of them is returned.
+    pub fn closest_match_in<'a, T>(
+        &self,
+        variants: impl Iterator<Item = (ModifierSet<&'a str>, T)>,
+    ) -> Option<T> {
+        Self::max_by_score(variants, |set| Some(self.overlap_score(set)))
+    }
+
+    /// Scores how well `candidate` matches `self`, as used by
+    /// [`best_match_in`](Self::best_match_in). Higher is better.
+    ///
+    /// Returns `None` if `candidate` is not a match at all.
+    pub(crate) fn match_score(
+        &self,
+        candidate: ModifierSet<&str>,
+    ) -> Option<(usize, std::cmp::Reverse<usize>)> {
+        self.is_subset(candidate).then(|| self.overlap_score(candidate))
+    }
+
+    /// Scores `candidate` by the number of modifiers it has in common with
+    /// `self` and by its total number of modifiers.
+    fn overlap_score(
+        &self,
+        candidate: ModifierSet<&str>,
+    ) -> (usize, std::cmp::Reverse<usize>) {
+        let mut matching = 0;
+        let mut total = 0;
+        for modifier in candidate.iter() {
+            if self.contains(modifier) {
+                matching += 1;
+            }
+            total += 1;
+        }
+
+        (matching, std::cmp::Reverse(total))
+    }
+
+    /// Returns the first of the variants with the highest score.
+    fn max_by_score<'a, T>(
+        variants: impl Iterator<Item = (ModifierSet<&'a str>, T)>,
+        mut score: impl FnMut(
+            ModifierSet<&'a str>,
+        ) -> Option<(usize, std::cmp::Reverse<usize>)>,
     ) -> Option<T> {
         let mut best = None;
         let mut best_score = None;
 
         // Find the best table entry with this name.
-        for candidate in variants.filter(|(set, _)| self.is_subset(*set)) {
-            let mut matching = 0;
-            let mut total = 0;
-            for modifier in candidate.0.iter() {
-                if self.contains(modifier) {
-                    matching += 1;
-                }
-                total += 1;
-            }
-
-            let score = (matching, std::cmp::Reverse(total));
+        for (set, value) in variants {
+            let Some(score) = score(set) else { continue };
             if best_score.is_none_or(|b| score > b) {
-                best = Some(candidate.1);
+                best = Some(value);
                 best_score = Some(score);
             }
         }
@@ -233,4 +295,47 @@ mod tests {
             Some(1)
         );
     }
+
+    #[test]
+    fn exact_match() {
+        let variants = [
+            (ModifierSet::from_raw_dotted(""), 0),
+            (ModifierSet::from_raw_dotted("a"), 1),
+            (ModifierSet::from_raw_dotted("a.b"), 2),
+        ];
+        assert_eq!(ModifierSet::default().exact_match_in(variants.into_iter()), Some(0));
+        assert_eq!(
+            ModifierSet::from_raw_dotted("b.a").exact_match_in(variants.into_iter()),
+            Some(2)
+        );
+        // no extra modifiers are filled in
+        assert_eq!(
+            ModifierSet::from_raw_dotted("b").exact_match_in(variants.into_iter()),
+            None
+        );
+    }
+
+    #[test]
+    fn closest_match() {
+        let variants = [
+            (ModifierSet::from_raw_dotted("a"), 1),
+            (ModifierSet::from_raw_dotted("a.b"), 2),
+            (ModifierSet::from_raw_dotted("b.c.d"), 3),
+        ];
+        // same as the best match if there is one
+        assert_eq!(
+            ModifierSet::from_raw_dotted("b").closest_match_in(variants.into_iter()),
+            Some(2)
+        );
+        // otherwise, the most modifiers in common
+        assert_eq!(
+            ModifierSet::from_raw_dotted("a.b.x").closest_match_in(variants.into_iter()),
+            Some(2)
+        );
+        // and then fewer modifiers in general
+        assert_eq!(
+            ModifierSet::from_raw_dotted("x").closest_match_in(variants.into_iter()),
+            Some(1)
+        );
+    }
 }
@@ -0,0 +1,210 @@
+//! Expand Typst-style shorthands like `->` into symbols.
+//!
+//! Typst lets users write some common symbols with sequences of ASCII
+//! characters, which are called _shorthands_. Which shorthands are available
+//! depends on the [`Context`]: Markup only has a few shorthands for
+//! typographic symbols, while math has many more for arrows and relations.
+//!
+//! ```
+//! use codex::shorthands::Context;
+//!
+//! assert_eq!(Context::Math.get("=>"), Some("sym.arrow.r.double"));
+//! assert_eq!(Context::Math.expand("a <= b -> c"), "a ≤ b → c");
+//! assert_eq!(Context::Markup.expand("wait... --- what?"), "wait… — what?");
+//! ```
+
+use std::ops::Range;
+
+/// The context in which shorthands are written.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Context {
+    /// Markup, i.e. running text.
+    Markup,
+    /// Math.
+    Math,
+}
+
+impl Context {
+    /// Iterate over the shorthands that are available in this context,
+    /// alongside the fully qualified paths of their symbols.
+    pub fn shorthands(self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.table().iter().map(|&(shorthand, path, _)| (shorthand, path))
+    }
+
+    /// Get the fully qualified path of the symbol for a shorthand, like
+    /// `sym.arrow.r` for `->`.
+    pub fn get(self, shorthand: &str) -> Option<&'static str> {
+        self.table()
+            .iter()
+            .find(|&&(s, _, _)| s == shorthand)
+            .map(|&(_, path, _)| path)
+    }
+
+    /// Find all shorthands in a text.
+    ///
+    /// The text is scanned from left to right. At each position, the longest
+    /// shorthand that starts there is taken, so `-->` is one shorthand in
+    /// math instead of `-` followed by `->`. Each match is represented by a
+    /// tuple `(range, path, value)`, where `range` is the byte range of the
+    /// shorthand in the text and `path` is the fully qualified path of its
+    /// symbol.
+    pub fn scan(self, text: &str) -> Scan<'_> {
+        Scan { table: self.table(), text, cursor: 0 }
+    }
+
+    /// Replace all shorthands in a text with their symbols.
+    ///
+    /// See [`scan`](Self::scan) for how shorthands are found.
+    pub fn expand(self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, _, value) in self.scan(text) {
+            out.push_str(&text[last..range.start]);
+            out.push_str(value);
+            last = range.end;
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// The table of shorthands for this context.
+    fn table(self) -> &'static [(&'static str, &'static str, &'static str)] {
+        match self {
+            Self::Markup => MARKUP,
+            Self::Math => MATH,
+        }
+    }
+}
+
+/// An iterator over the shorthands in a text, created by [`Context::scan`].
+#[derive(Debug, Clone)]
+pub struct Scan<'a> {
+    table: &'static [(&'static str, &'static str, &'static str)],
+    text: &'a str,
+    cursor: usize,
+}
+
+impl Iterator for Scan<'_> {
+    type Item = (Range<usize>, &'static str, &'static str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.cursor < self.text.len() {
+            let rest = &self.text[self.cursor..];
+            let longest = self
+                .table
+                .iter()
+                .filter(|&&(shorthand, _, _)| rest.starts_with(shorthand))
+                .max_by_key(|&&(shorthand, _, _)| shorthand.len());
+            let start = self.cursor;
+            if let Some(&(shorthand, path, value)) = longest {
+                self.cursor += shorthand.len();
+                return Some((start..self.cursor, path, value));
+            }
+            self.cursor += rest.chars().next().unwrap().len_utf8();
+        }
+        None
+    }
+}
+
+/// Builds a table of shorthands, resolving the value of each symbol at compile
+/// time.
+macro_rules! table {
+    ($($shorthand:literal => $path:literal,)*) => {
+        &[$(($shorthand, $path, crate::macros::resolve(crate::ROOT, $path).0),)*]
+    };
+}
+
+/// The shorthands in markup.
+const MARKUP: &[(&str, &str, &str)] = table! {
+    "~" => "sym.space.nobreak",
+    "-?" => "sym.hyph.soft",
+    "--" => "sym.dash.en",
+    "---" => "sym.dash.em",
+    "..." => "sym.dots.h",
+};
+
+/// The shorthands in math.
+const MATH: &[(&str, &str, &str)] = table! {
+    "->" => "sym.arrow.r",
+    "-->" => "sym.arrow.r.long",
+    "|->" => "sym.arrow.r.bar",
+    "=>" => "sym.arrow.r.double",
+    "==>" => "sym.arrow.r.double.long",
+    "|=>" => "sym.arrow.r.double.bar",
+    "<-" => "sym.arrow.l",
+    "<--" => "sym.arrow.l.long",
+    "<==" => "sym.arrow.l.double.long",
+    "<->" => "sym.arrow.l.r",
+    "<-->" => "sym.arrow.l.r.long",
+    "<=>" => "sym.arrow.l.r.double",
+    "<==>" => "sym.arrow.l.r.double.long",
+    "->>" => "sym.arrow.r.twohead",
+    "<<-" => "sym.arrow.l.twohead",
+    ">->" => "sym.arrow.r.tail",
+    "<-<" => "sym.arrow.l.tail",
+    "~>" => "sym.arrow.r.squiggly",
+    "<~" => "sym.arrow.l.squiggly",
+    "~~>" => "sym.arrow.r.long.squiggly",
+    "<~~" => "sym.arrow.l.long.squiggly",
+    ":=" => "sym.colon.eq",
+    "::=" => "sym.colon.double.eq",
+    "=:" => "sym.eq.colon",
+    "!=" => "sym.eq.not",
+    "<=" => "sym.lt.eq",
+    ">=" => "sym.gt.eq",
+    "<<" => "sym.lt.double",
+    ">>" => "sym.gt.double",
+    "<<<" => "sym.lt.triple",
+    ">>>" => "sym.gt.triple",
+    "[|" => "sym.bracket.stroked.l",
+    "|]" => "sym.bracket.stroked.r",
+    "||" => "sym.bar.v.double",
+    "..." => "sym.dots.h",
+    "*" => "sym.ast.op",
+    "-" => "sym.minus",
+    "'" => "sym.prime",
+    "~" => "sym.tilde.op",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shorthands_are_unique() {
+        for context in [Context::Markup, Context::Math] {
+            let mut seen = std::collections::HashSet::new();
+            for (shorthand, _) in context.shorthands() {
+                assert!(seen.insert(shorthand), "duplicate shorthand {shorthand:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn shorthands_are_not_deprecated() {
+        for context in [Context::Markup, Context::Math] {
+            for (shorthand, path) in context.shorthands() {
+                let (_, deprecation) =
+                    crate::macros::lookup(crate::ROOT, path.as_bytes()).unwrap();
+                assert_eq!(deprecation, None, "{shorthand:?} maps to {path}");
+            }
+        }
+    }
+
+    #[test]
+    fn longest_match() {
+        assert_eq!(Context::Math.expand("a-->b"), "a⟶b");
+        assert_eq!(Context::Math.expand("a--b"), "a−−b");
+        assert_eq!(Context::Math.expand("<==>"), "⟺");
+        assert_eq!(Context::Math.expand("x <= y"), "x ≤ y");
+        assert_eq!(Context::Markup.expand("a----b"), "a—-b");
+        assert_eq!(Context::Markup.expand("ä~ö"), "ä\u{a0}ö");
+    }
+
+    #[test]
+    fn scan() {
+        let matches: Vec<_> = Context::Math.scan("x != y").collect();
+        assert_eq!(matches, [(2..4, "sym.eq.not", "≠")]);
+        assert_eq!(Context::Markup.scan("a -> b").count(), 0);
+    }
+}
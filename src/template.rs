@@ -0,0 +1,187 @@
+//! Expand placeholders like `:sym.arrow.r:` in text.
+//!
+//! A placeholder is a fully qualified path, like `sym.arrow.r` or
+//! `emoji.rocket`, between two delimiters. The path is resolved through
+//! [`ROOT`] like with the [`sym!`](crate::sym) macro, so the
+//! modifiers may be in any order and need not all be given.
+//!
+//! ```
+//! # #[cfg(all(feature = "sym", feature = "emoji"))] {
+//! use codex::template::Template;
+//!
+//! let expansion = Template::new().expand("Ship it :emoji.rocket: :sym.arrow.r: done");
+//! assert_eq!(expansion.text, "Ship it 🚀 → done");
+//! assert!(expansion.diagnostics.is_empty());
+//!
+//! let expansion = Template::new().delimiters("{{", "}}").expand("a {{sym.arrow.foo}} b");
+//! assert_eq!(expansion.text, "a {{sym.arrow.foo}} b");
+//! assert_eq!(expansion.diagnostics[0].span, 2..19);
+//! # }
+//! ```
+
+use std::ops::Range;
+
+use crate::ROOT;
+
+/// Expands placeholders in text.
+///
+/// Only text between delimiters that looks like a path, like `sym.arrow` or
+/// `smy.arrow`, or that is the name of a top-level module, like `sym`, is a
+/// placeholder. Paths that don't resolve, e.g. because of a misspelled module,
+/// are reported. Everything else is left alone, so that e.g. times like
+/// `10:30:00` and words like `:smile:` are kept as they are with the default
+/// delimiters `:` and `:`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Template<'a> {
+    open: &'a str,
+    close: &'a str,
+}
+
+impl Default for Template<'_> {
+    fn default() -> Self {
+        Self { open: ":", close: ":" }
+    }
+}
+
+impl<'a> Template<'a> {
+    /// Create a template with the default delimiters `:` and `:`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use other delimiters, like `{{` and `}}`.
+    ///
+    /// # Panics
+    /// Panics if either delimiter is empty.
+    pub fn delimiters(self, open: &'a str, close: &'a str) -> Self {
+        assert!(!open.is_empty() && !close.is_empty(), "delimiters must not be empty");
+        Self { open, close }
+    }
+
+    /// Expand all placeholders in a text.
+    ///
+    /// Placeholders that can't be resolved are kept as they are and reported
+    /// with an error. Placeholders for deprecated symbols or variants are
+    /// expanded, but reported with their deprecation message.
+    pub fn expand(&self, text: &str) -> Expansion {
+        let mut expansion = Expansion { text: String::new(), diagnostics: vec![] };
+        let mut last = 0;
+        let mut cursor = 0;
+        while let Some(offset) = text[cursor..].find(self.open) {
+            let start = cursor + offset;
+            let inner = start + self.open.len();
+            let Some(len) = text[inner..].find(self.close) else { break };
+            let path = &text[inner..inner + len];
+            if !is_placeholder(path) {
+                cursor = inner;
+                continue;
+            }
+
+            let end = inner + len + self.close.len();
+            let span = start..end;
+            match crate::macros::lookup(ROOT, path.as_bytes()) {
+                Ok((value, deprecation)) => {
+                    expansion.text.push_str(&text[last..start]);
+                    expansion.text.push_str(value);
+                    last = end;
+                    if let Some(message) = deprecation {
+                        let kind = DiagnosticKind::Deprecated(message);
+                        expansion.diagnostics.push(Diagnostic { span, kind });
+                    }
+                }
+                Err(message) => {
+                    let kind = DiagnosticKind::Error(message);
+                    expansion.diagnostics.push(Diagnostic { span, kind });
+                }
+            }
+            cursor = end;
+        }
+        expansion.text.push_str(&text[last..]);
+        expansion
+    }
+}
+
+/// The result of [`Template::expand`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Expansion {
+    /// The text with all resolvable placeholders replaced.
+    pub text: String,
+    /// Problems with the placeholders, in the order they occur in.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A problem with a placeholder.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Diagnostic {
+    /// The byte range of the placeholder in the original text, including the
+    /// delimiters.
+    pub span: Range<usize>,
+    /// What the problem is.
+    pub kind: DiagnosticKind,
+}
+
+/// The kind of a [`Diagnostic`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DiagnosticKind {
+    /// The placeholder couldn't be resolved, for the given reason.
+    Error(&'static str),
+    /// The placeholder refers to a deprecated symbol or variant, with the
+    /// given deprecation message.
+    Deprecated(&'static str),
+}
+
+/// Whether the text between two delimiters is meant as a placeholder.
+fn is_placeholder(path: &str) -> bool {
+    path.split('.')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphabetic()))
+        && (path.contains('.') || ROOT.get(path).is_some())
+}
+
+#[cfg(all(test, feature = "sym"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand() {
+        let template = Template::new();
+        assert_eq!(template.expand(":sym.arrow.r.double:").text, "⇒");
+        assert_eq!(template.expand(":sym.arrow.double.r:").text, "⇒");
+        assert_eq!(template.expand("at 10:30:00").text, "at 10:30:00");
+        assert_eq!(template.expand("a:b :sym.lt.eq: c").text, "a:b ≤ c");
+        assert_eq!(template.expand("::sym.dash.em::").text, ":—:");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let expansion = Template::new().expand("x :sym.arrow.double: y :sym.spacebar:");
+        assert_eq!(expansion.text, "x :sym.arrow.double: y ␣");
+        assert_eq!(
+            expansion
+                .diagnostics
+                .iter()
+                .map(|d| d.span.clone())
+                .collect::<Vec<_>>(),
+            [2..20, 23..37],
+        );
+        assert!(matches!(expansion.diagnostics[0].kind, DiagnosticKind::Error(_)));
+        assert!(matches!(expansion.diagnostics[1].kind, DiagnosticKind::Deprecated(_)));
+
+        let expansion = Template::new().expand("a :smy.arrow: b :smile: c");
+        assert_eq!(expansion.text, "a :smy.arrow: b :smile: c");
+        assert_eq!(expansion.diagnostics.len(), 1);
+        assert_eq!(expansion.diagnostics[0].span, 2..13);
+        assert_eq!(
+            expansion.diagnostics[0].kind,
+            DiagnosticKind::Error("unknown symbol or module"),
+        );
+    }
+
+    #[test]
+    fn delimiters() {
+        let template = Template::new().delimiters("${", "}");
+        assert_eq!(
+            template.expand("${sym.dash.em} :sym.dash.em:").text,
+            "— :sym.dash.em:"
+        );
+    }
+}
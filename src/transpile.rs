@@ -0,0 +1,175 @@
+//! Convert Unicode text into codex notation.
+//!
+//! This is the reverse of looking up symbols: Given a text like `∀x ∈ ℝ`, a
+//! [`Transpiler`] replaces each character that has a name with that name,
+//! producing source for Typst's math mode like `forall x in RR`.
+//!
+//! ```
+//! # #[cfg(feature = "sym")] {
+//! use codex::transpile::{Transpiler, Unnamed};
+//!
+//! let text = "∀x ∈ ℝ: x² ≥ 0";
+//! assert_eq!(Transpiler::new().transpile(text), "forall x in RR: x² gt.eq 0");
+//! assert_eq!(
+//!     Transpiler::new().prefix(true).unnamed(Unnamed::Escape).transpile(text),
+//!     r"sym.forall x sym.in sym.RR: x\u{b2} sym.gt.eq 0",
+//! );
+//! # }
+//! ```
+
+use std::fmt::Write;
+
+use crate::{LONGEST_NAMED_VALUE, ModifierSet, NAMES};
+
+/// Get the preferred name of a value.
+///
+/// Returns the fully qualified path of the symbol and the modifiers of the
+/// preferred variant with the given value. A variant is preferred if it isn't
+/// deprecated and isn't an alias, and then if its name is the shortest.
+/// Deprecated variants and ASCII values never have a preferred name.
+///
+/// ```
+/// # #[cfg(feature = "sym")] {
+/// # use codex::transpile::name;
+/// let (path, modifiers) = name("⇒").unwrap();
+/// assert_eq!(path, "sym.arrow");
+/// assert_eq!(modifiers.as_str(), "r.double");
+/// # }
+/// ```
+pub fn name(value: &str) -> Option<(&'static str, ModifierSet<&'static str>)> {
+    NAMES
+        .binary_search_by_key(&value, |&(value, ..)| value)
+        .ok()
+        .map(|i| (NAMES[i].1, NAMES[i].2))
+}
+
+/// What to do with non-ASCII characters that have no name.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Unnamed {
+    /// Keep them as they are.
+    #[default]
+    Keep,
+    /// Replace them with Typst escape sequences like `\u{b2}`.
+    Escape,
+    /// Remove them.
+    Drop,
+}
+
+/// Converts Unicode text into codex notation.
+///
+/// ASCII text is left alone. Everything else is replaced with the preferred
+/// [`name`] of the longest value that matches at each position. Names are
+/// separated from adjacent letters, digits, and other names with spaces, so
+/// that the result can be used in Typst's math mode.
+///
+/// Names of emoji always have the `emoji.` prefix, since they aren't
+/// available without it in math mode. By default, names of general symbols
+/// don't have the `sym.` prefix.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Transpiler {
+    prefix: bool,
+    unnamed: Unnamed,
+}
+
+impl Transpiler {
+    /// Create a transpiler with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to also prefix names of general symbols with `sym.`.
+    pub fn prefix(self, prefix: bool) -> Self {
+        Self { prefix, ..self }
+    }
+
+    /// What to do with non-ASCII characters that have no name.
+    pub fn unnamed(self, unnamed: Unnamed) -> Self {
+        Self { unnamed, ..self }
+    }
+
+    /// Convert a text.
+    pub fn transpile(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            let Some((len, path, modifiers)) = longest_match(rest) else {
+                match self.unnamed {
+                    _ if c.is_ascii() => out.push(c),
+                    Unnamed::Keep => out.push(c),
+                    Unnamed::Escape => write!(out, "\\u{{{:x}}}", c as u32).unwrap(),
+                    Unnamed::Drop => {}
+                }
+                rest = &rest[c.len_utf8()..];
+                continue;
+            };
+
+            if out.ends_with(is_name_char) {
+                out.push(' ');
+            }
+            let path = if self.prefix {
+                path
+            } else {
+                path.strip_prefix("sym.").unwrap_or(path)
+            };
+            out.push_str(path);
+            for modifier in modifiers {
+                out.push('.');
+                out.push_str(modifier);
+            }
+            rest = &rest[len..];
+            if rest.starts_with(|c: char| is_name_char(c) || c == '.' || c == '(') {
+                out.push(' ');
+            }
+        }
+        out
+    }
+}
+
+/// Finds the longest value that `text` starts with, returning its length and
+/// preferred name.
+fn longest_match(text: &str) -> Option<(usize, &'static str, ModifierSet<&'static str>)> {
+    (1..LONGEST_NAMED_VALUE + 1).rev().find_map(|len| {
+        let (path, modifiers) = name(text.get(..len)?)?;
+        Some((len, path, modifiers))
+    })
+}
+
+/// Whether a character can be part of a name or would join with one.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(all(test, feature = "sym", feature = "emoji"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for &(value, path, modifiers) in NAMES {
+            let mut full = path.to_string();
+            for modifier in modifiers {
+                full.push('.');
+                full.push_str(modifier);
+            }
+            let (resolved, deprecation) =
+                crate::macros::lookup(crate::ROOT, full.as_bytes()).unwrap();
+            assert_eq!(resolved, value, "{full}");
+            assert_eq!(deprecation, None, "{full}");
+        }
+        let longest = NAMES.iter().map(|(value, ..)| value.len()).max();
+        assert_eq!(longest, Some(LONGEST_NAMED_VALUE));
+    }
+
+    #[test]
+    fn transpile() {
+        let t = Transpiler::new();
+        assert_eq!(t.transpile("a → b"), "a arrow.r b");
+        assert_eq!(t.transpile("x∈A"), "x in A");
+        assert_eq!(t.transpile("∀∃"), "forall exists");
+        assert_eq!(t.transpile("ℝ(x)"), "RR (x)");
+        assert_eq!(t.transpile("a -> b"), "a -> b");
+        assert_eq!(t.transpile("😀"), "emoji.face.grin");
+        assert_eq!(t.prefix(true).transpile("∞"), "sym.oo");
+        assert_eq!(t.unnamed(Unnamed::Drop).transpile("x²"), "x");
+    }
+}
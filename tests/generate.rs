@@ -0,0 +1,59 @@
+//! Copies files that the build script generates into the source tree.
+//!
+//! The build script only checks the files in `src/modules` and leaves the
+//! corrected versions, as well as a reference of all symbols, in its output
+//! directory. These tests copy them over and are ignored by default, so they
+//! have to be run explicitly, e.g. with
+//! `cargo test --test generate -- --ignored format`.
+
+use std::path::Path;
+
+/// Copies a file from the output directory of the build script to a path
+/// relative to the root of the package.
+fn update(name: &str, dest: &str) {
+    let src = Path::new(env!("OUT_DIR")).join(name);
+    let dest = Path::new(env!("CARGO_MANIFEST_DIR")).join(dest);
+    std::fs::copy(src, dest).unwrap();
+}
+
+/// Formats the definition files in the canonical layout.
+#[test]
+#[ignore = "writes into the source tree"]
+fn format() {
+    update("sym.txt", "src/modules/sym.txt");
+    update("emoji.txt", "src/modules/emoji.txt");
+}
+
+/// Assigns IDs to new variants in the ID manifest.
+#[test]
+#[ignore = "writes into the source tree"]
+fn ids() {
+    update("ids.txt", "src/modules/ids.txt");
+}
+
+/// Replaces the release manifest with the current variants, which is done for
+/// every release. This needs all definition files to be enabled.
+#[cfg(all(feature = "sym", feature = "emoji"))]
+#[test]
+#[ignore = "writes into the source tree"]
+fn release() {
+    update("release.txt", "src/modules/release.txt");
+}
+
+/// Writes a Markdown and an HTML reference of all symbols to the directory in
+/// the `CODEX_REFERENCE` environment variable, or to `target/reference` if it
+/// isn't set.
+#[test]
+#[ignore = "writes files to disk"]
+fn reference() {
+    let dest = std::env::var_os("CODEX_REFERENCE").map_or_else(
+        || Path::new(env!("CARGO_MANIFEST_DIR")).join("target/reference"),
+        Into::into,
+    );
+    std::fs::create_dir_all(&dest).unwrap();
+    let src = Path::new(env!("OUT_DIR")).join("reference");
+    for entry in std::fs::read_dir(src).unwrap() {
+        let entry = entry.unwrap();
+        std::fs::copy(entry.path(), dest.join(entry.file_name())).unwrap();
+    }
+}